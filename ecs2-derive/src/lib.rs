@@ -0,0 +1,297 @@
+//! Derive macros for `ecs2`. These are re-exported by `ecs2` when its `derive` feature is
+//! enabled, so this crate shouldn't need to be used directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam, Lifetime, LitStr,
+};
+
+/// The storage kinds that a component can choose with `#[component(storage = "...")]`.
+const STORAGE_KINDS: &[&str] = &["sparse_set"];
+
+/// Derive `Component`.
+///
+/// The storage can be chosen with `#[component(storage = "sparse_set")]`. Sparse sets are
+/// currently the only kind of storage, and the default.
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    if let Err(error) = parse_component_attrs(&input) {
+        return error.to_compile_error().into();
+    }
+
+    impl_trait(&input, quote!(::ecs2::storage::component::Component)).into()
+}
+
+/// Derive `Unique`.
+#[proc_macro_derive(Unique)]
+pub fn derive_unique(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_trait(&input, quote!(::ecs2::storage::unique::Unique)).into()
+}
+
+/// Derive `WorldData`. The type must also implement `Default`.
+#[proc_macro_derive(WorldData)]
+pub fn derive_world_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_trait(&input, quote!(::ecs2::world::WorldData)).into()
+}
+
+/// Derive `Query` for a struct whose fields are all queries.
+///
+/// The struct must have exactly one lifetime parameter, which is the lifetime of the world
+/// borrow. Fields are borrowed in order, and the first error is returned. The struct can
+/// be used with any world data type that all of its fields can be borrowed from.
+#[proc_macro_derive(Query)]
+pub fn derive_query(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_query(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn impl_query(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "`Query` can only be derived for structs",
+        ));
+    };
+
+    let mut lifetimes = input.generics.lifetimes();
+    let (Some(lifetime), None) = (lifetimes.next(), lifetimes.next()) else {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`Query` structs must have exactly one lifetime parameter",
+        ));
+    };
+    let lifetime = &lifetime.lifetime;
+
+    let field_tys: Vec<_> = data.fields.iter().map(|field| &field.ty).collect();
+    let borrows = data.fields.iter().map(|field| {
+        let ty = &field.ty;
+        quote!(<#ty as ::ecs2::query::Query<#lifetime, __Data>>::borrow(world)?)
+    });
+    let construct = match &data.fields {
+        Fields::Named(_) => {
+            let idents = data.fields.iter().map(|field| &field.ident);
+            quote!(#name { #(#idents: #borrows,)* })
+        }
+        Fields::Unnamed(_) => quote!(#name ( #(#borrows,)* )),
+        Fields::Unit => quote!(#name),
+    };
+
+    // `Query` is implemented for the struct's own lifetime.
+    let mut query_generics = input.generics.clone();
+    query_generics
+        .params
+        .push(parse_quote!(__Data: ::ecs2::world::WorldData));
+    {
+        let where_clause = query_generics.make_where_clause();
+        for ty in &field_tys {
+            where_clause
+                .predicates
+                .push(parse_quote!(#ty: ::ecs2::query::Query<#lifetime, __Data>));
+        }
+    }
+    let (impl_generics, _, where_clause) = query_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    // `QueryFamily` is implemented for any lifetime, and names the struct's lifetime in
+    // its `Item` type instead.
+    let family_lifetime = Lifetime::new("'__family", lifetime.span());
+    let mut family_generics = input.generics.clone();
+    for param in family_generics.params.iter_mut() {
+        if let GenericParam::Lifetime(param) = param {
+            param.lifetime = family_lifetime.clone();
+            param.bounds.clear();
+        }
+    }
+    family_generics
+        .params
+        .push(parse_quote!(__Data: ::ecs2::world::WorldData));
+    {
+        let where_clause = family_generics.make_where_clause();
+        for ty in &field_tys {
+            where_clause.predicates.push(parse_quote!(
+                for<#lifetime> #ty: ::ecs2::query::Query<#lifetime, __Data> + ::std::marker::Send
+            ));
+        }
+    }
+    let (family_impl_generics, _, family_where_clause) = family_generics.split_for_impl();
+    let family_ty_generics = input.generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(_) => quote!(#family_lifetime),
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::ecs2::query::Query<#lifetime, __Data> for #name #ty_generics
+        #where_clause
+        {
+            #[inline]
+            fn borrow(
+                world: &#lifetime ::ecs2::world::World<__Data>,
+            ) -> ::ecs2::query::QueryResult<Self> {
+                ::std::result::Result::Ok(#construct)
+            }
+
+            #[inline]
+            fn access(access: &mut ::ecs2::query::access::Access) {
+                #(<#field_tys as ::ecs2::query::Query<#lifetime, __Data>>::access(access);)*
+            }
+        }
+
+        impl #family_impl_generics ::ecs2::query::QueryFamily<__Data>
+            for #name<#(#family_ty_generics),*>
+        #family_where_clause
+        {
+            type Item<#lifetime> = #name #ty_generics;
+        }
+    })
+}
+
+/// Derive `Bundle` for a struct whose fields are all bundles (usually components).
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_bundle(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn impl_bundle(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "`Bundle` can only be derived for structs",
+        ));
+    };
+
+    let bundle = quote!(::ecs2::storage::bundle::Bundle);
+
+    let field_tys: Vec<_> = data.fields.iter().map(|field| &field.ty).collect();
+    let members: Vec<_> = data.fields.members().collect();
+    let indices: Vec<_> = (0..field_tys.len()).map(syn::Index::from).collect();
+    let bindings: Vec<_> = (0..field_tys.len())
+        .map(|i| quote::format_ident!("__field{}", i))
+        .collect();
+
+    let construct = match &data.fields {
+        Fields::Named(_) | Fields::Unnamed(_) => quote!(#name { #(#members: #bindings,)* }),
+        Fields::Unit => quote!(#name),
+    };
+
+    let mut generics = input.generics.clone();
+    {
+        let where_clause = generics.make_where_clause();
+        for ty in &field_tys {
+            where_clause.predicates.push(parse_quote!(#ty: #bundle));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #bundle for #name #ty_generics #where_clause {
+            type Storages<'__a> = (#(<#field_tys as #bundle>::Storages<'__a>,)*);
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn borrow_storages(
+                all_storages: &::ecs2::storage::bundle::AllStorages,
+            ) -> ::ecs2::query::QueryResult<Self::Storages<'_>> {
+                ::std::result::Result::Ok((
+                    #(<#field_tys as #bundle>::borrow_storages(all_storages)?,)*
+                ))
+            }
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn reserve(
+                storages: &mut Self::Storages<'_>,
+                entities: &[::ecs2::storage::entities::EntityId],
+            ) {
+                #(<#field_tys as #bundle>::reserve(&mut storages.#indices, entities);)*
+            }
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn insert(
+                self,
+                storages: &mut Self::Storages<'_>,
+                entity: ::ecs2::storage::entities::EntityId,
+            ) -> ::ecs2::query::QueryResult<()> {
+                #(<#field_tys as #bundle>::insert(self.#members, &mut storages.#indices, entity)?;)*
+                ::std::result::Result::Ok(())
+            }
+
+            #[allow(unused_variables, unreachable_patterns)]
+            #[inline]
+            fn remove(
+                storages: &mut Self::Storages<'_>,
+                entity: ::ecs2::storage::entities::EntityId,
+            ) -> ::ecs2::query::QueryResult<::std::option::Option<Self>> {
+                #(
+                    let #bindings =
+                        <#field_tys as #bundle>::remove(&mut storages.#indices, entity)?;
+                )*
+                ::std::result::Result::Ok(match (#(#bindings,)*) {
+                    (#(::std::option::Option::Some(#bindings),)*) => {
+                        ::std::option::Option::Some(#construct)
+                    }
+                    _ => ::std::option::Option::None,
+                })
+            }
+        }
+    })
+}
+
+fn impl_trait(input: &DeriveInput, trait_path: TokenStream2) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {}
+    }
+}
+
+fn parse_component_attrs(input: &DeriveInput) -> syn::Result<()> {
+    for attr in &input.attrs {
+        if !attr.path().is_ident("component") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("storage") {
+                let storage: LitStr = meta.value()?.parse()?;
+                if !STORAGE_KINDS.contains(&storage.value().as_str()) {
+                    return Err(syn::Error::new(
+                        storage.span(),
+                        format!(
+                            "unknown storage kind, expected one of: {}",
+                            STORAGE_KINDS.join(", ")
+                        ),
+                    ));
+                }
+                Ok(())
+            } else {
+                Err(meta.error("unknown component attribute"))
+            }
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use std::any::{Any, TypeId};
+
+use crate::erased_storages::AllStorages;
+use crate::snapshot::Snapshot;
+use crate::sparse::SparseSet;
+use crate::storage::component::{Component, ComponentStorage, TrackedComponent};
+use crate::storage::entities::{EntityError, EntityId, EntityStorage};
+use crate::world::{World, WorldData};
+
+type DiffFn = fn(
+    Option<&(dyn Any + Send + Sync)>,
+    &EntityStorage,
+    Option<&(dyn Any + Send + Sync)>,
+    &EntityStorage,
+) -> Option<Box<dyn Any + Send + Sync>>;
+type ApplyFn = fn(&mut AllStorages, &(dyn Any + Send + Sync));
+type AllAliveFn = fn(&(dyn Any + Send + Sync), &dyn Fn(EntityId) -> bool) -> bool;
+
+/// How to diff and patch the storage of a registered diffable component type.
+#[derive(Clone, Copy)]
+pub(crate) struct ComponentDiff {
+    diff: DiffFn,
+    apply: ApplyFn,
+    all_alive: AllAliveFn,
+}
+
+impl ComponentDiff {
+    pub fn of<C: Component + Clone + PartialEq>() -> Self {
+        Self {
+            diff: diff_components::<C>,
+            apply: apply_components::<C>,
+            all_alive: all_alive::<C>,
+        }
+    }
+}
+
+/// The changes between two [snapshots](Snapshot), which can be applied to another world
+/// to bring it up to date.
+///
+/// Entities keep their ids: an entity spawned in the newer snapshot is spawned with the
+/// same index and version wherever the delta is applied. Only components whose type was
+/// registered with [`diffable`](crate::registry::ComponentConfig::diffable) are included.
+pub struct Delta {
+    spawned: Vec<EntityId>,
+    despawned: Vec<EntityId>,
+    components: Vec<ComponentDelta>,
+}
+
+struct ComponentDelta {
+    type_id: TypeId,
+    changes: Box<dyn Any + Send + Sync>,
+    apply: ApplyFn,
+    all_alive: AllAliveFn,
+}
+
+/// The changes to one type of component in a [`Delta`].
+///
+/// Components of entities that were despawned aren't listed as removed, and components of
+/// entities that were spawned are listed as added.
+pub struct ComponentChanges<C> {
+    added: Vec<(EntityId, C)>,
+    changed: Vec<(EntityId, C)>,
+    removed: Vec<EntityId>,
+}
+
+impl<C> ComponentChanges<C> {
+    /// Components that were inserted on entities that didn't have one.
+    #[inline]
+    pub fn added(&self) -> &[(EntityId, C)] {
+        &self.added
+    }
+
+    /// The new values of components that compare unequal to their old values.
+    #[inline]
+    pub fn changed(&self) -> &[(EntityId, C)] {
+        &self.changed
+    }
+
+    /// Entities that are still alive but no longer have the component.
+    #[inline]
+    pub fn removed(&self) -> &[EntityId] {
+        &self.removed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Delta {
+    /// Entities that are alive in the newer snapshot but not in the older one.
+    #[inline]
+    pub fn spawned(&self) -> &[EntityId] {
+        &self.spawned
+    }
+
+    /// Entities that are alive in the older snapshot but not in the newer one.
+    #[inline]
+    pub fn despawned(&self) -> &[EntityId] {
+        &self.despawned
+    }
+
+    /// Get the changes to a type of component, or `None` if it didn't change.
+    pub fn components<C: Component>(&self) -> Option<&ComponentChanges<C>> {
+        self.components
+            .iter()
+            .find(|delta| delta.type_id == TypeId::of::<C>())
+            .and_then(|delta| delta.changes.downcast_ref())
+    }
+
+    /// Check if nothing changed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty() && self.despawned.is_empty() && self.components.is_empty()
+    }
+}
+
+impl Snapshot {
+    /// Compute the changes from this snapshot to a newer one.
+    ///
+    /// Components are compared with [`PartialEq`], so a component that was modified and
+    /// then set back to its old value isn't included.
+    pub fn diff(&self, newer: &Snapshot) -> Delta {
+        let spawned = newer
+            .entities
+            .iter()
+            .filter(|&entity| !self.entities.is_alive(entity))
+            .collect();
+        let despawned = self
+            .entities
+            .iter()
+            .filter(|&entity| !newer.entities.is_alive(entity))
+            .collect();
+
+        let components = newer
+            .components
+            .iter()
+            .filter_map(|new| {
+                let component_diff = new.diff?;
+                let old = self
+                    .components
+                    .iter()
+                    .find(|old| old.type_id == new.type_id)
+                    .and_then(|old| old.data.as_deref());
+
+                let changes = (component_diff.diff)(
+                    old,
+                    &self.entities,
+                    new.data.as_deref(),
+                    &newer.entities,
+                )?;
+
+                Some(ComponentDelta {
+                    type_id: new.type_id,
+                    changes,
+                    apply: component_diff.apply,
+                    all_alive: component_diff.all_alive,
+                })
+            })
+            .collect();
+
+        Delta {
+            spawned,
+            despawned,
+            components,
+        }
+    }
+}
+
+impl<Data: WorldData> World<Data> {
+    /// Apply a [`Delta`] to the world.
+    ///
+    /// The world's entities should match the older snapshot the delta was computed from,
+    /// for example because the world is a replica that has only ever been updated with
+    /// deltas. Entities are despawned first, then spawned with their original ids, and
+    /// then components are inserted and removed, marking them as added, changed or removed
+    /// as usual.
+    ///
+    /// Reserved entities are made alive first. Nothing is changed if an entity to despawn
+    /// is already dead, or an entity to spawn has an index that's in use and isn't being
+    /// despawned, or a version older than its index's, or if a component change names an
+    /// entity that won't be alive once the delta is applied.
+    pub fn apply_delta(&mut self, delta: &Delta) -> Result<(), EntityError> {
+        self.all_storages.entities.flush_reserved();
+        let entities = &self.all_storages.entities;
+
+        if !delta
+            .despawned
+            .iter()
+            .all(|&entity| entities.is_alive(entity))
+        {
+            return Err(EntityError::DeadEntity);
+        }
+        entities.check_alloc_exact(&delta.spawned, &delta.despawned)?;
+
+        // Component changes can only name entities that are alive once the delta's
+        // entities have been despawned and spawned.
+        let mut despawned = delta.despawned.clone();
+        despawned.sort_unstable_by_key(EntityId::index);
+        let mut spawned = delta.spawned.clone();
+        spawned.sort_unstable_by_key(EntityId::index);
+        let contains = |list: &[EntityId], entity: EntityId| {
+            list.binary_search_by_key(&entity.index(), EntityId::index)
+                .is_ok_and(|i| list[i] == entity)
+        };
+        let alive_after = |entity| {
+            contains(&spawned, entity)
+                || (entities.is_alive(entity) && !contains(&despawned, entity))
+        };
+        if !delta.components.iter().all(|component_delta| {
+            (component_delta.all_alive)(&*component_delta.changes, &alive_after)
+        }) {
+            return Err(EntityError::DeadEntity);
+        }
+
+        for &entity in &delta.despawned {
+            self.all_storages.despawn(entity)?;
+        }
+
+        self.all_storages.entities.alloc_exact(&delta.spawned)?;
+
+        for component_delta in &delta.components {
+            (component_delta.apply)(&mut self.all_storages, &*component_delta.changes);
+        }
+
+        Ok(())
+    }
+}
+
+fn diff_components<C: Component + Clone + PartialEq>(
+    old: Option<&(dyn Any + Send + Sync)>,
+    old_entities: &EntityStorage,
+    new: Option<&(dyn Any + Send + Sync)>,
+    new_entities: &EntityStorage,
+) -> Option<Box<dyn Any + Send + Sync>> {
+    let old = old.and_then(|data| data.downcast_ref::<SparseSet<TrackedComponent<C>>>());
+    let new = new.and_then(|data| data.downcast_ref::<SparseSet<TrackedComponent<C>>>());
+
+    let mut changes = ComponentChanges {
+        added: vec![],
+        changed: vec![],
+        removed: vec![],
+    };
+
+    for (index, tracked) in new.into_iter().flat_map(SparseSet::iter_with_indices) {
+        let Some(entity) = new_entities.alive_at(index) else {
+            continue;
+        };
+
+        // A component at the same index belongs to a different entity if the old one
+        // was despawned.
+        let old_component = old
+            .filter(|_| old_entities.is_alive(entity))
+            .and_then(|old| old.get(index));
+
+        match old_component {
+            Some(old) if old.component == tracked.component => {}
+            Some(_) => changes.changed.push((entity, tracked.component.clone())),
+            None => changes.added.push((entity, tracked.component.clone())),
+        }
+    }
+
+    for (index, _) in old.into_iter().flat_map(SparseSet::iter_with_indices) {
+        let Some(entity) = old_entities.alive_at(index) else {
+            continue;
+        };
+
+        if new_entities.is_alive(entity) && !new.is_some_and(|new| new.contains(index)) {
+            changes.removed.push(entity);
+        }
+    }
+
+    if changes.is_empty() {
+        None
+    } else {
+        Some(Box::new(changes))
+    }
+}
+
+fn all_alive<C: Component>(
+    changes: &(dyn Any + Send + Sync),
+    is_alive: &dyn Fn(EntityId) -> bool,
+) -> bool {
+    let changes = changes
+        .downcast_ref::<ComponentChanges<C>>()
+        .expect("component changes have the wrong type");
+
+    changes
+        .added
+        .iter()
+        .chain(&changes.changed)
+        .map(|&(entity, _)| entity)
+        .chain(changes.removed.iter().copied())
+        .all(is_alive)
+}
+
+fn apply_components<C: Component + Clone>(
+    all_storages: &mut AllStorages,
+    changes: &(dyn Any + Send + Sync),
+) {
+    let changes = changes
+        .downcast_ref::<ComponentChanges<C>>()
+        .expect("component changes have the wrong type");
+    let tick = all_storages.change_tick.get();
+    let log = all_storages.removed.get::<C>();
+
+    if !all_storages.components.contains::<ComponentStorage<C>>() {
+        let _ = all_storages
+            .components
+            .insert(ComponentStorage::<C>::default());
+    }
+    let storage = all_storages
+        .components
+        .get_mut::<ComponentStorage<C>>()
+        .unwrap();
+
+    for (entity, component) in changes.added.iter().chain(&changes.changed) {
+        storage.insert(entity.index(), component.clone(), tick);
+    }
+
+    for &entity in &changes.removed {
+        let _ = storage.remove(entity, log);
+    }
+}
@@ -1,8 +1,12 @@
+use crate::entity_ref::EntityRef;
 use crate::erased_storages::AllStorages;
 use crate::prelude::{Component, EntityId};
-use crate::query::QueryResult;
+use crate::query::component::Mut;
+use crate::query::{QueryError, QueryResult};
+use crate::storage::bundle::Bundle;
 use crate::storage::component::ComponentStorage;
-use std::cell::RefMut;
+use crate::storage::entities::EntityError;
+use atomic_refcell::AtomicRefMut;
 
 pub struct EntityMut<'a> {
     pub(crate) all_storages: &'a mut AllStorages,
@@ -11,21 +15,94 @@ pub struct EntityMut<'a> {
 
 impl<'a> EntityMut<'a> {
     pub fn insert<C: Component>(self, component: C) -> QueryResult<Self> {
-        let mut components: RefMut<ComponentStorage<C>> =
+        let mut components: AtomicRefMut<ComponentStorage<C>> =
             self.all_storages.components.borrow_mut_or_insert().unwrap();
-        components.0.insert(self.entity.index(), component);
+        let tick = self.all_storages.change_tick.get();
+        components.insert(self.entity.index(), component, tick);
         drop(components);
         Ok(self)
     }
 
     pub fn remove<C: Component>(self) -> QueryResult<Self> {
-        let mut components: RefMut<ComponentStorage<C>> =
+        let mut components: AtomicRefMut<ComponentStorage<C>> =
             self.all_storages.components.borrow_mut()?;
-        let _ = components.0.remove(self.entity.index());
+        let _ = components.remove(self.entity, self.all_storages.removed.get::<C>());
         drop(components);
         Ok(self)
     }
 
+    /// Remove a component from this entity and return it, if it had one.
+    pub fn take<C: Component>(&mut self) -> Option<C> {
+        let log = self.all_storages.removed.get::<C>();
+        self.all_storages
+            .components
+            .get_mut::<ComponentStorage<C>>()?
+            .remove(self.entity, log)
+    }
+
+    /// Get one of this entity's components.
+    ///
+    /// No runtime borrow is needed, since this has exclusive access to the world. Fails
+    /// with [`QueryError::EntityMissing`] if the entity doesn't have the component.
+    #[inline]
+    pub fn get<C: Component>(&mut self) -> QueryResult<&C> {
+        self.all_storages
+            .components
+            .get_mut::<ComponentStorage<C>>()
+            .and_then(|components| components.get(self.entity.index()))
+            .ok_or(QueryError::EntityMissing)
+    }
+
+    /// Borrow one of this entity's components mutably, marking it as changed if it's
+    /// written to.
+    ///
+    /// Fails with [`QueryError::EntityMissing`] if the entity doesn't have the component.
+    pub fn get_mut<C: Component>(&mut self) -> QueryResult<Mut<'_, C>> {
+        let tick = self.all_storages.change_tick.get();
+        self.all_storages
+            .components
+            .get_mut::<ComponentStorage<C>>()
+            .and_then(|components| components.get_mut(self.entity.index(), tick))
+            .ok_or(QueryError::EntityMissing)
+    }
+
+    /// Check whether this entity has a component.
+    #[inline]
+    pub fn contains<C: Component>(&mut self) -> bool {
+        self.get::<C>().is_ok()
+    }
+
+    /// Get read-only access to this entity.
+    #[inline]
+    pub fn as_ref(&self) -> EntityRef<'_> {
+        EntityRef {
+            all_storages: self.all_storages,
+            entity: self.entity,
+        }
+    }
+
+    /// Insert every component in a bundle.
+    pub fn insert_bundle<B: Bundle>(self, bundle: B) -> QueryResult<Self> {
+        let mut storages = B::borrow_storages(self.all_storages)?;
+        bundle.insert(&mut storages, self.entity)?;
+        drop(storages);
+        Ok(self)
+    }
+
+    /// Remove every component in a bundle that this entity has.
+    pub fn remove_bundle<B: Bundle>(self) -> QueryResult<Self> {
+        let mut storages = B::borrow_storages(self.all_storages)?;
+        let _ = B::remove(&mut storages, self.entity)?;
+        drop(storages);
+        Ok(self)
+    }
+
+    /// Despawn this entity, removing all of its components.
+    #[inline]
+    pub fn despawn(self) -> Result<(), EntityError> {
+        self.all_storages.despawn(self.entity)
+    }
+
     #[inline]
     pub fn id(&self) -> EntityId {
         self.entity
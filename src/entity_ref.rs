@@ -0,0 +1,51 @@
+use atomic_refcell::AtomicRef;
+
+use crate::erased_storages::AllStorages;
+use crate::prelude::{Component, EntityId};
+use crate::query::{QueryError, QueryResult};
+use crate::storage::component::ComponentStorage;
+
+/// Read-only access to a single entity's components.
+pub struct EntityRef<'a> {
+    pub(crate) all_storages: &'a AllStorages,
+    pub(crate) entity: EntityId,
+}
+
+impl<'a> EntityRef<'a> {
+    /// Borrow one of this entity's components.
+    ///
+    /// Fails with [`QueryError::EntityMissing`] if the entity doesn't have the component.
+    pub fn get<C: Component>(&self) -> QueryResult<AtomicRef<'a, C>> {
+        let components = match self
+            .all_storages
+            .components
+            .borrow_ref::<ComponentStorage<C>>()
+        {
+            Ok(components) => components,
+            Err(QueryError::StorageMissing) => return Err(QueryError::EntityMissing),
+            Err(error) => return Err(error),
+        };
+
+        let index = self.entity.index();
+        AtomicRef::filter_map(components, |components| components.get(index))
+            .ok_or(QueryError::EntityMissing)
+    }
+
+    /// Check whether this entity has a component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component's storage is borrowed mutably.
+    pub fn contains<C: Component>(&self) -> bool {
+        match self.get::<C>() {
+            Ok(_) => true,
+            Err(QueryError::EntityMissing) => false,
+            Err(error) => panic!("failed to check for component: {error}"),
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> EntityId {
+        self.entity
+    }
+}
@@ -1,15 +1,20 @@
 use std::any::Any;
 
-use crate::storage::component::{Component, ComponentStorage};
+use crate::storage::component::{Component, ComponentInfo, ComponentStorage, RemovalLogs};
 use crate::storage::entities::EntityId;
 
 use super::storage_map::ErasableStorage;
 
-trait ErasedComponentStorageTrait: Any {
+trait ErasedComponentStorageTrait: Any + Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
 
-    fn remove_entity(&mut self, entity: EntityId);
+    fn remove_entity(&mut self, entity: EntityId, logs: &RemovalLogs);
+
+    fn contains(&self, entity: EntityId) -> bool;
+
+    fn info(&self) -> ComponentInfo;
 }
 
 impl<C: Component> ErasedComponentStorageTrait for ComponentStorage<C> {
@@ -21,8 +26,20 @@ impl<C: Component> ErasedComponentStorageTrait for ComponentStorage<C> {
         self
     }
 
-    fn remove_entity(&mut self, entity: EntityId) {
-        self.0.remove(entity.index());
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn remove_entity(&mut self, entity: EntityId, logs: &RemovalLogs) {
+        self.remove(entity, logs.get::<C>());
+    }
+
+    fn contains(&self, entity: EntityId) -> bool {
+        self.set.contains(entity.index())
+    }
+
+    fn info(&self) -> ComponentInfo {
+        ComponentInfo::of::<C>()
     }
 }
 
@@ -41,8 +58,20 @@ impl ErasedComponentStorage {
         (*self.0).as_any_mut().downcast_mut()
     }
 
-    pub fn remove_entity(&mut self, entity: EntityId) {
-        (*self.0).remove_entity(entity);
+    pub fn downcast<S: Any>(self) -> Option<S> {
+        self.0.into_any().downcast().ok().map(|storage| *storage)
+    }
+
+    pub fn remove_entity(&mut self, entity: EntityId, logs: &RemovalLogs) {
+        (*self.0).remove_entity(entity, logs);
+    }
+
+    pub fn contains(&self, entity: EntityId) -> bool {
+        (*self.0).contains(entity)
+    }
+
+    pub fn info(&self) -> ComponentInfo {
+        (*self.0).info()
     }
 }
 
@@ -63,4 +92,9 @@ impl<C: Component> ErasableStorage for ComponentStorage<C> {
     fn downcast_mut(erased: &mut Self::ErasedStorage) -> Option<&mut Self> {
         erased.downcast_mut()
     }
+
+    #[inline]
+    fn downcast(erased: Self::ErasedStorage) -> Option<Self> {
+        erased.downcast()
+    }
 }
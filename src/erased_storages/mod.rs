@@ -1,4 +1,7 @@
-use crate::storage::entities::EntityStorage;
+use crate::query::commands::CommandQueue;
+use crate::storage::component::RemovalLogs;
+use crate::storage::entities::{EntityError, EntityId, EntityStorage};
+use crate::storage::tick::AtomicTick;
 
 use self::component::ErasedComponentStorage;
 use self::storage_map::StorageMap;
@@ -10,8 +13,42 @@ mod unique;
 mod storage_map;
 
 #[derive(Default)]
-pub(crate) struct AllStorages {
+pub struct AllStorages {
     pub(crate) entities: EntityStorage,
     pub(crate) components: StorageMap<ErasedComponentStorage>,
+    pub(crate) removed: RemovalLogs,
     pub(crate) uniques: StorageMap<ErasedUniqueStorage>,
+    pub(crate) change_tick: AtomicTick,
+    pub(crate) commands: CommandQueue,
+}
+
+impl AllStorages {
+    /// Despawn an entity, removing all of its components.
+    pub(crate) fn despawn(&mut self, entity: EntityId) -> Result<(), EntityError> {
+        self.entities.dealloc(entity)?;
+
+        for storage in self.components.iter_mut() {
+            storage.remove_entity(entity, &self.removed);
+        }
+
+        Ok(())
+    }
+
+    /// Spawn reserved entities and apply all recorded commands.
+    ///
+    /// Every command is applied even if an earlier one fails, and the first error is
+    /// returned.
+    pub(crate) fn apply_commands(&mut self) -> Result<(), EntityError> {
+        self.entities.flush_reserved();
+
+        let mut result = Ok(());
+        for command in self.commands.take() {
+            let command_result = command(self);
+            if result.is_ok() {
+                result = command_result;
+            }
+        }
+
+        result
+    }
 }
@@ -1,8 +1,8 @@
 use std::any::{Any, TypeId};
-use std::cell::{Ref, RefCell, RefMut};
-use std::slice::{Iter, IterMut};
+use std::collections::hash_map::ValuesMut;
 
-use elsa::FrozenMap;
+use atomic_refcell::{AtomicRef, AtomicRefCell, AtomicRefMut};
+use elsa::sync::FrozenMap;
 
 use crate::query::{QueryError, QueryResult};
 
@@ -13,10 +13,11 @@ pub(crate) trait ErasableStorage: Any + Sized {
 
     fn downcast_ref(erased: &Self::ErasedStorage) -> Option<&Self>;
     fn downcast_mut(erased: &mut Self::ErasedStorage) -> Option<&mut Self>;
+    fn downcast(erased: Self::ErasedStorage) -> Option<Self>;
 }
 
 pub(crate) struct StorageMap<ErasedStorage> {
-    storages: FrozenMap<TypeId, Box<RefCell<ErasedStorage>>>,
+    storages: FrozenMap<TypeId, Box<AtomicRefCell<ErasedStorage>>>,
 }
 
 impl<ErasedStorage> Default for StorageMap<ErasedStorage> {
@@ -28,44 +29,99 @@ impl<ErasedStorage> Default for StorageMap<ErasedStorage> {
 }
 
 impl<ErasedStorage> StorageMap<ErasedStorage> {
-    pub fn insert<S: ErasableStorage<ErasedStorage = ErasedStorage>>(&self, storage: S) {
+    /// Insert a storage, returning the one it replaced, if any.
+    pub fn insert<S: ErasableStorage<ErasedStorage = ErasedStorage>>(
+        &mut self,
+        storage: S,
+    ) -> Option<S> {
         let type_id = TypeId::of::<S>();
+        let old = self
+            .storages
+            .as_mut()
+            .insert(type_id, Box::new(AtomicRefCell::new(storage.erase())))?;
+        S::downcast(old.into_inner())
+    }
+
+    /// Remove a storage.
+    ///
+    /// The map is append-only while it's shared, so this needs exclusive access.
+    pub fn remove<S: ErasableStorage<ErasedStorage = ErasedStorage>>(&mut self) -> Option<S> {
+        let type_id = TypeId::of::<S>();
+        let erased_storage = self.storages.as_mut().remove(&type_id)?;
+        S::downcast(erased_storage.into_inner())
+    }
+
+    /// Move every storage from another map into this one, replacing existing ones.
+    #[cfg(feature = "serde")]
+    pub fn append(&mut self, other: Self) {
         self.storages
-            .insert(type_id, Box::new(RefCell::new(storage.erase())));
+            .as_mut()
+            .extend(other.storages.into_tuple_vec());
+    }
+
+    #[inline]
+    pub fn contains<S: ErasableStorage<ErasedStorage = ErasedStorage>>(&self) -> bool {
+        self.get::<S>().is_ok()
     }
 
     pub fn borrow_ref<S: ErasableStorage<ErasedStorage = ErasedStorage>>(
         &self,
-    ) -> QueryResult<Ref<S>> {
+    ) -> QueryResult<AtomicRef<'_, S>> {
         let erased_storage = self.get::<S>()?;
         borrow_ref(erased_storage)
     }
 
     pub fn borrow_mut<S: ErasableStorage<ErasedStorage = ErasedStorage>>(
         &self,
-    ) -> QueryResult<RefMut<S>> {
+    ) -> QueryResult<AtomicRefMut<'_, S>> {
         let erased_storage = self.get::<S>()?;
         borrow_mut(erased_storage)
     }
 
     pub fn borrow_ref_or_insert<S: ErasableStorage<ErasedStorage = ErasedStorage> + Default>(
         &self,
-    ) -> QueryResult<Ref<S>> {
+    ) -> QueryResult<AtomicRef<'_, S>> {
         let erased_storage = self.get_or_insert::<S>();
         borrow_ref(erased_storage)
     }
 
     pub fn borrow_mut_or_insert<S: ErasableStorage<ErasedStorage = ErasedStorage> + Default>(
         &self,
-    ) -> QueryResult<RefMut<S>> {
+    ) -> QueryResult<AtomicRefMut<'_, S>> {
         let erased_storage = self.get_or_insert::<S>();
         borrow_mut(erased_storage)
     }
 
+    /// Borrow every storage in the map, one at a time.
+    pub fn iter_ref(&self) -> impl Iterator<Item = QueryResult<AtomicRef<'_, ErasedStorage>>> {
+        self.storages.keys_cloned().into_iter().map(|type_id| {
+            // Storages are never removed through a shared reference.
+            let erased_storage = self.storages.get(&type_id).unwrap();
+            Ok(erased_storage.try_borrow()?)
+        })
+    }
+
+    /// Get a storage mutably, without runtime borrow checking since we have exclusive
+    /// access.
+    #[inline]
+    pub fn get_mut<S: ErasableStorage<ErasedStorage = ErasedStorage>>(&mut self) -> Option<&mut S> {
+        let type_id = TypeId::of::<S>();
+        let erased_storage = self.storages.as_mut().get_mut(&type_id)?;
+        S::downcast_mut(erased_storage.get_mut())
+    }
+
+    /// Iterate mutably over every storage in the map.
+    ///
+    /// No runtime borrow checking is needed because we have exclusive access.
+    #[inline]
+    pub fn iter_mut(&mut self) -> ErasedStorageIterMut<'_, ErasedStorage> {
+        ErasedStorageIterMut(self.storages.as_mut().values_mut())
+    }
+
     #[inline]
     fn get<S: ErasableStorage<ErasedStorage = ErasedStorage>>(
         &self,
-    ) -> QueryResult<&RefCell<ErasedStorage>> {
+    ) -> QueryResult<&AtomicRefCell<ErasedStorage>> {
         let type_id = TypeId::of::<S>();
         self.storages
             .get(&type_id)
@@ -75,47 +131,36 @@ impl<ErasedStorage> StorageMap<ErasedStorage> {
     #[inline]
     fn get_or_insert<S: ErasableStorage<ErasedStorage = ErasedStorage> + Default>(
         &self,
-    ) -> &RefCell<ErasedStorage> {
+    ) -> &AtomicRefCell<ErasedStorage> {
         let type_id = TypeId::of::<S>();
+        // Another thread may insert the storage first, in which case that one is kept.
         self.storages.get(&type_id).unwrap_or_else(|| {
-            self.storages
-                .insert(type_id, Box::new(RefCell::new(S::default().erase())))
+            self.storages.insert_with(type_id, || {
+                Box::new(AtomicRefCell::new(S::default().erase()))
+            })
         })
     }
 }
 
-pub(crate) struct ErasedStorageIter<'a, ErasedStorage>(Iter<'a, RefCell<ErasedStorage>>);
-
-impl<'a, ErasedStorage> Iterator for ErasedStorageIter<'a, ErasedStorage> {
-    type Item = QueryResult<Ref<'a, ErasedStorage>>;
-
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0
-            .next()
-            .map(|erased_storage| erased_storage.try_borrow().map_err(From::from))
-    }
-}
-
-pub(crate) struct ErasedStorageIterMut<'a, ErasedStorage>(IterMut<'a, RefCell<ErasedStorage>>);
+pub(crate) struct ErasedStorageIterMut<'a, ErasedStorage>(
+    ValuesMut<'a, TypeId, Box<AtomicRefCell<ErasedStorage>>>,
+);
 
 impl<'a, ErasedStorage> Iterator for ErasedStorageIterMut<'a, ErasedStorage> {
-    type Item = QueryResult<RefMut<'a, ErasedStorage>>;
+    type Item = &'a mut ErasedStorage;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.0
-            .next()
-            .map(|erased_storage| erased_storage.try_borrow_mut().map_err(From::from))
+        self.0.next().map(|erased_storage| erased_storage.get_mut())
     }
 }
 
 #[inline]
 fn borrow_ref<S: ErasableStorage>(
-    erased_storage: &RefCell<S::ErasedStorage>,
-) -> QueryResult<Ref<S>> {
+    erased_storage: &AtomicRefCell<S::ErasedStorage>,
+) -> QueryResult<AtomicRef<'_, S>> {
     let erased_storage_ref = erased_storage.try_borrow()?;
-    let storage = Ref::map(erased_storage_ref, |erased| {
+    let storage = AtomicRef::map(erased_storage_ref, |erased| {
         S::downcast_ref(erased).unwrap()
     });
     Ok(storage)
@@ -123,10 +168,10 @@ fn borrow_ref<S: ErasableStorage>(
 
 #[inline]
 fn borrow_mut<S: ErasableStorage>(
-    erased_storage: &RefCell<S::ErasedStorage>,
-) -> QueryResult<RefMut<S>> {
+    erased_storage: &AtomicRefCell<S::ErasedStorage>,
+) -> QueryResult<AtomicRefMut<'_, S>> {
     let erased_storage_mut = erased_storage.try_borrow_mut()?;
-    let storage = RefMut::map(erased_storage_mut, |erased| {
+    let storage = AtomicRefMut::map(erased_storage_mut, |erased| {
         S::downcast_mut(erased).unwrap()
     });
     Ok(storage)
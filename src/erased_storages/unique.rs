@@ -4,7 +4,7 @@ use crate::storage::unique::{Unique, UniqueStorage};
 
 use super::storage_map::ErasableStorage;
 
-pub(crate) struct ErasedUniqueStorage(Box<dyn Any>);
+pub(crate) struct ErasedUniqueStorage(Box<dyn Any + Send + Sync>);
 
 impl ErasedUniqueStorage {
     pub fn new<T: Unique>(storage: UniqueStorage<T>) -> Self {
@@ -18,6 +18,10 @@ impl ErasedUniqueStorage {
     pub fn downcast_mut<S: Any>(&mut self) -> Option<&mut S> {
         (*self.0).downcast_mut()
     }
+
+    pub fn downcast<S: Any>(self) -> Option<S> {
+        self.0.downcast().ok().map(|storage| *storage)
+    }
 }
 
 impl<C: Unique> ErasableStorage for UniqueStorage<C> {
@@ -37,4 +41,9 @@ impl<C: Unique> ErasableStorage for UniqueStorage<C> {
     fn downcast_mut(erased: &mut Self::ErasedStorage) -> Option<&mut Self> {
         erased.downcast_mut()
     }
+
+    #[inline]
+    fn downcast(erased: Self::ErasedStorage) -> Option<Self> {
+        erased.downcast()
+    }
 }
@@ -1,18 +1,41 @@
+pub mod delta;
 pub mod query;
+pub mod registry;
+pub mod schedule;
+pub mod snapshot;
 pub mod storage;
 pub mod world;
 
+pub use rayon;
+
+#[cfg(feature = "derive")]
+pub use ecs2_derive::{Bundle, Component, Query, Unique, WorldData};
+
 mod entity_mut;
+mod entity_ref;
 mod erased_storages;
+#[cfg(feature = "serde")]
+mod serialize;
 mod sparse;
 mod system;
 
 pub mod prelude {
+    pub use crate::entity_mut::EntityMut;
+    pub use crate::entity_ref::EntityRef;
+    pub use crate::query::commands::Commands;
     pub use crate::query::component::{QueryComp, QueryCompMut};
-    pub use crate::query::unique::{QueryUnique, QueryUniqueMut};
+    pub use crate::query::filter::{With, Without};
+    pub use crate::query::join::{Join, Optional, ParJoin};
+    pub use crate::query::removed::{RemovedComponents, RemovedCursor};
+    pub use crate::query::unique::{OptionalUnique, QueryUnique, QueryUniqueMut};
     pub use crate::query::Query;
+    pub use crate::schedule::Schedule;
+    pub use crate::storage::bundle::Bundle;
     pub use crate::storage::component::Component;
     pub use crate::storage::entities::EntityId;
     pub use crate::storage::unique::Unique;
     pub use crate::world::{World, WorldData};
+
+    #[cfg(feature = "derive")]
+    pub use ecs2_derive::{Bundle, Component, Query, Unique, WorldData};
 }
@@ -0,0 +1,175 @@
+use std::any::{type_name, TypeId};
+use std::fmt;
+
+use crate::storage::component::Component;
+use crate::storage::unique::Unique;
+
+/// A part of the world that a query can borrow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Component(TypeId),
+    /// The log of removals of a component type.
+    Removed(TypeId),
+    Unique(TypeId),
+    Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A single borrow made by a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceAccess {
+    pub resource: Resource,
+    pub name: &'static str,
+    pub kind: AccessKind,
+}
+
+impl ResourceAccess {
+    #[inline]
+    pub fn conflicts_with(&self, other: &ResourceAccess) -> bool {
+        self.resource == other.resource
+            && (self.kind == AccessKind::Write || other.kind == AccessKind::Write)
+    }
+}
+
+impl fmt::Display for ResourceAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => "reads",
+            AccessKind::Write => "writes",
+        };
+
+        match self.resource {
+            Resource::Component(_) => write!(f, "{kind} component `{}`", self.name),
+            Resource::Removed(_) => write!(f, "{kind} removals of component `{}`", self.name),
+            Resource::Unique(_) => write!(f, "{kind} unique `{}`", self.name),
+            Resource::Data => write!(f, "{kind} world data"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("conflicting access: {first} and {second}")]
+pub struct AccessConflict {
+    pub first: ResourceAccess,
+    pub second: ResourceAccess,
+}
+
+/// The set of borrows that a query or system makes, known without borrowing anything.
+///
+/// Conflicting borrows, for example writing the same component twice, are recorded as
+/// they're added and can be checked with [`Access::validate`].
+#[derive(Debug, Default, Clone)]
+pub struct Access {
+    accesses: Vec<ResourceAccess>,
+    conflicts: Vec<AccessConflict>,
+}
+
+impl Access {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn read_component<C: Component>(&mut self) {
+        self.add(
+            Resource::Component(TypeId::of::<C>()),
+            type_name::<C>(),
+            AccessKind::Read,
+        );
+    }
+
+    #[inline]
+    pub fn write_component<C: Component>(&mut self) {
+        self.add(
+            Resource::Component(TypeId::of::<C>()),
+            type_name::<C>(),
+            AccessKind::Write,
+        );
+    }
+
+    /// Record a read of a component's removal log.
+    ///
+    /// The log is locked while it's written to, so removing components doesn't need to
+    /// be declared, and doesn't conflict with this.
+    #[inline]
+    pub fn read_removed<C: Component>(&mut self) {
+        self.add(
+            Resource::Removed(TypeId::of::<C>()),
+            type_name::<C>(),
+            AccessKind::Read,
+        );
+    }
+
+    #[inline]
+    pub fn read_unique<T: Unique>(&mut self) {
+        self.add(
+            Resource::Unique(TypeId::of::<T>()),
+            type_name::<T>(),
+            AccessKind::Read,
+        );
+    }
+
+    #[inline]
+    pub fn write_unique<T: Unique>(&mut self) {
+        self.add(
+            Resource::Unique(TypeId::of::<T>()),
+            type_name::<T>(),
+            AccessKind::Write,
+        );
+    }
+
+    #[inline]
+    pub fn read_data(&mut self) {
+        self.add(Resource::Data, "world data", AccessKind::Read);
+    }
+
+    #[inline]
+    pub fn write_data(&mut self) {
+        self.add(Resource::Data, "world data", AccessKind::Write);
+    }
+
+    pub fn add(&mut self, resource: Resource, name: &'static str, kind: AccessKind) {
+        let access = ResourceAccess {
+            resource,
+            name,
+            kind,
+        };
+
+        if let Some(first) = self.accesses.iter().find(|a| a.conflicts_with(&access)) {
+            self.conflicts.push(AccessConflict {
+                first: *first,
+                second: access,
+            });
+        }
+
+        self.accesses.push(access);
+    }
+
+    /// Iterate over every borrow, in the order they were added.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &ResourceAccess> {
+        self.accesses.iter()
+    }
+
+    /// Check whether any borrow here conflicts with a borrow in `other`.
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        self.accesses
+            .iter()
+            .any(|a| other.accesses.iter().any(|b| a.conflicts_with(b)))
+    }
+
+    /// Check that no two borrows conflict with each other.
+    #[inline]
+    pub fn validate(&self) -> Result<(), AccessConflict> {
+        match self.conflicts.first() {
+            Some(conflict) => Err(*conflict),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::{Mutex, PoisonError};
+
+use crate::entity_mut::EntityMut;
+use crate::erased_storages::AllStorages;
+use crate::query::access::Access;
+use crate::query::{Query, QueryFamily, QueryResult};
+use crate::storage::component::Component;
+use crate::storage::entities::{EntityError, EntityId, EntityStorage};
+use crate::storage::unique::{Unique, UniqueStorage};
+use crate::world::{World, WorldData};
+
+type Command = Box<dyn FnOnce(&mut AllStorages) -> Result<(), EntityError> + Send>;
+
+/// Commands that have been recorded but not yet applied to the world.
+#[derive(Default)]
+pub(crate) struct CommandQueue(Mutex<Vec<Command>>);
+
+impl CommandQueue {
+    #[inline]
+    pub fn take(&mut self) -> Vec<Command> {
+        std::mem::take(self.0.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// A query that records changes to make to the world later.
+///
+/// The commands are applied in the order they were recorded when
+/// [`World::apply_commands`] is called. Entities spawned with [`Commands::spawn`] are
+/// reserved straight away, so their ids can be used by later commands.
+pub struct Commands<'a> {
+    queue: &'a CommandQueue,
+    entities: &'a EntityStorage,
+    commands: Vec<Command>,
+}
+
+impl<'a, D: WorldData> Query<'a, D> for Commands<'a> {
+    #[inline]
+    fn borrow(world: &'a World<D>) -> QueryResult<Self> {
+        Ok(Commands {
+            queue: &world.all_storages.commands,
+            entities: &world.all_storages.entities,
+            commands: vec![],
+        })
+    }
+
+    /// Commands don't borrow any storages until they're applied.
+    #[inline]
+    fn access(_access: &mut Access) {}
+}
+
+impl<D: WorldData> QueryFamily<D> for Commands<'_> {
+    type Item<'a> = Commands<'a>;
+}
+
+impl Commands<'_> {
+    /// Reserve an entity, which will be spawned when the commands are applied.
+    #[inline]
+    pub fn spawn(&mut self) -> Result<EntityId, EntityError> {
+        self.entities.reserve()
+    }
+
+    /// Despawn an entity, removing all of its components.
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.push(move |all_storages| all_storages.despawn(entity));
+    }
+
+    /// Insert a component into an entity.
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.push(move |all_storages| {
+            entity_mut(all_storages, entity)?
+                .insert(component)
+                .expect("storage should not be borrowed");
+            Ok(())
+        });
+    }
+
+    /// Remove a component from an entity.
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.push(move |all_storages| {
+            // The storage is only missing if the component was never inserted.
+            let _ = entity_mut(all_storages, entity)?.remove::<C>();
+            Ok(())
+        });
+    }
+
+    /// Insert a unique, replacing any existing one.
+    pub fn insert_unique<T: Unique>(&mut self, unique: T) {
+        self.push(move |all_storages| {
+            let _ = all_storages.uniques.insert(UniqueStorage(unique));
+            Ok(())
+        });
+    }
+
+    #[inline]
+    fn push(
+        &mut self,
+        command: impl FnOnce(&mut AllStorages) -> Result<(), EntityError> + Send + 'static,
+    ) {
+        self.commands.push(Box::new(command));
+    }
+}
+
+impl Drop for Commands<'_> {
+    fn drop(&mut self) {
+        self.queue
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .append(&mut self.commands);
+    }
+}
+
+#[inline]
+fn entity_mut(
+    all_storages: &mut AllStorages,
+    entity: EntityId,
+) -> Result<EntityMut<'_>, EntityError> {
+    if !all_storages.entities.is_alive(entity) {
+        return Err(EntityError::DeadEntity);
+    }
+
+    Ok(EntityMut {
+        all_storages,
+        entity,
+    })
+}
@@ -1,15 +1,21 @@
-use std::cell::{Ref, RefMut};
+use atomic_refcell::{AtomicRef, AtomicRefMut};
+use rayon::prelude::*;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
 
+use crate::erased_storages::AllStorages;
+use crate::query::access::Access;
 use crate::query::{QueryError, QueryResult};
-use crate::storage::component::{Component, ComponentStorage};
+use crate::storage::component::{Component, ComponentStorage, RemovalLog};
 use crate::storage::entities::{EntityId, EntityStorage};
+use crate::storage::tick::{ComponentTicks, Tick};
 use crate::world::{World, WorldData};
 
-use super::Query;
+use super::{Query, QueryFamily};
 
 pub struct QueryComp<'a, C: Component> {
-    storage: Ref<'a, ComponentStorage<C>>,
-    entities: &'a EntityStorage,
+    pub(super) storage: AtomicRef<'a, ComponentStorage<C>>,
+    pub(super) entities: &'a EntityStorage,
 }
 
 impl<'a, C: Component, D: WorldData> Query<'a, D> for QueryComp<'a, C> {
@@ -19,22 +25,40 @@ impl<'a, C: Component, D: WorldData> Query<'a, D> for QueryComp<'a, C> {
         let entities = &world.all_storages.entities;
         Ok(QueryComp { storage, entities })
     }
+
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read_component::<C>();
+    }
+}
+
+impl<C: Component, D: WorldData> QueryFamily<D> for QueryComp<'_, C> {
+    type Item<'a> = QueryComp<'a, C>;
 }
 
 pub struct QueryCompMut<'a, C: Component> {
-    storage: RefMut<'a, ComponentStorage<C>>,
-    entities: &'a EntityStorage,
+    pub(super) storage: AtomicRefMut<'a, ComponentStorage<C>>,
+    pub(super) entities: &'a EntityStorage,
+    pub(super) tick: Tick,
+    removed: &'a Mutex<RemovalLog>,
 }
 
 impl<'a, C: Component, D: WorldData> Query<'a, D> for QueryCompMut<'a, C> {
     #[inline]
     fn borrow(world: &'a World<D>) -> QueryResult<Self> {
-        let storage = world.all_storages.components.borrow_mut_or_insert()?;
-        let entities = &world.all_storages.entities;
-        Ok(QueryCompMut { storage, entities })
+        Self::borrow_from(&world.all_storages)
+    }
+
+    #[inline]
+    fn access(access: &mut Access) {
+        access.write_component::<C>();
     }
 }
 
+impl<C: Component, D: WorldData> QueryFamily<D> for QueryCompMut<'_, C> {
+    type Item<'a> = QueryCompMut<'a, C>;
+}
+
 impl<C: Component> QueryComp<'_, C> {
     #[inline]
     pub fn get(&self, entity: EntityId) -> QueryResult<&C> {
@@ -42,23 +66,78 @@ impl<C: Component> QueryComp<'_, C> {
             return Err(QueryError::EntityDead);
         }
         self.storage
-            .0
             .get(entity.index())
             .ok_or(QueryError::EntityMissing)
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &C> {
-        self.storage.0.iter()
+        self.storage.iter()
+    }
+
+    /// Iterate over the components along with the entities they belong to.
+    #[inline]
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (EntityId, &C)> {
+        iter_with_ids(&self.storage, self.entities)
+    }
+
+    /// Iterate over the components that were added at or after a tick.
+    #[inline]
+    pub fn iter_added_since(&self, tick: Tick) -> impl Iterator<Item = (EntityId, &C)> {
+        iter_since(&self.storage, self.entities, move |ticks| {
+            ticks.added >= tick
+        })
+    }
+
+    /// Iterate over the components that were added or changed at or after a tick.
+    #[inline]
+    pub fn iter_changed_since(&self, tick: Tick) -> impl Iterator<Item = (EntityId, &C)> {
+        iter_since(&self.storage, self.entities, move |ticks| {
+            ticks.changed >= tick
+        })
+    }
+}
+
+impl<'a, C: Component> QueryCompMut<'a, C> {
+    #[inline]
+    pub(crate) fn borrow_from(all_storages: &'a AllStorages) -> QueryResult<Self> {
+        let storage = all_storages.components.borrow_mut_or_insert()?;
+        let entities = &all_storages.entities;
+        let tick = all_storages.change_tick.get();
+        let removed = all_storages.removed.get::<C>();
+        Ok(QueryCompMut {
+            storage,
+            entities,
+            tick,
+            removed,
+        })
     }
 }
 
 impl<C: Component> QueryCompMut<'_, C> {
+    #[inline]
+    pub(crate) fn reserve(&mut self, entities: &[EntityId]) {
+        self.storage.reserve(entities);
+    }
+
     #[inline]
     pub fn insert(&mut self, entity: EntityId, component: C) -> QueryResult<Option<C>> {
         if !self.entities.is_alive(entity) {
             return Err(QueryError::EntityDead);
         }
-        Ok(self.storage.0.insert(entity.index(), component))
+        Ok(self.storage.insert(entity.index(), component, self.tick))
+    }
+
+    /// Remove a component, returning it if there was one.
+    ///
+    /// The removal is recorded so that it can be seen by [`RemovedComponents`].
+    ///
+    /// [`RemovedComponents`]: crate::query::removed::RemovedComponents
+    #[inline]
+    pub fn remove(&mut self, entity: EntityId) -> QueryResult<Option<C>> {
+        if !self.entities.is_alive(entity) {
+            return Err(QueryError::EntityDead);
+        }
+        Ok(self.storage.remove(entity, self.removed))
     }
 
     #[inline]
@@ -68,30 +147,173 @@ impl<C: Component> QueryCompMut<'_, C> {
         }
 
         self.storage
-            .0
             .get(entity.index())
             .ok_or(QueryError::EntityMissing)
     }
 
+    /// Get a component mutably.
+    ///
+    /// The component is only marked as changed if it is actually mutated through the
+    /// returned [`Mut`].
     #[inline]
-    pub fn get_mut(&mut self, entity: EntityId) -> QueryResult<&mut C> {
+    pub fn get_mut(&mut self, entity: EntityId) -> QueryResult<Mut<'_, C>> {
         if !self.entities.is_alive(entity) {
             return Err(QueryError::EntityDead);
         }
 
         self.storage
-            .0
-            .get_mut(entity.index())
+            .get_mut(entity.index(), self.tick)
             .ok_or(QueryError::EntityMissing)
     }
 
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = &C> {
-        self.storage.0.iter()
+        self.storage.iter()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = Mut<'_, C>> {
+        self.storage.iter_mut(self.tick)
+    }
+
+    /// Iterate over the components along with the entities they belong to.
+    #[inline]
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (EntityId, &C)> {
+        iter_with_ids(&self.storage, self.entities)
+    }
+
+    /// Iterate mutably over the components along with the entities they belong to.
+    #[inline]
+    pub fn iter_mut_with_ids(&mut self) -> impl Iterator<Item = (EntityId, Mut<'_, C>)> {
+        let entities = self.entities;
+        self.storage
+            .iter_mut_with_indices(self.tick)
+            .filter_map(|(index, component)| Some((entities.alive_at(index)?, component)))
     }
 
+    /// Iterate over the components that were added at or after a tick.
     #[inline]
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut C> {
-        self.storage.0.iter_mut()
+    pub fn iter_added_since(&self, tick: Tick) -> impl Iterator<Item = (EntityId, &C)> {
+        iter_since(&self.storage, self.entities, move |ticks| {
+            ticks.added >= tick
+        })
+    }
+
+    /// Iterate over the components that were added or changed at or after a tick.
+    #[inline]
+    pub fn iter_changed_since(&self, tick: Tick) -> impl Iterator<Item = (EntityId, &C)> {
+        iter_since(&self.storage, self.entities, move |ticks| {
+            ticks.changed >= tick
+        })
+    }
+
+    /// Iterate mutably over the components in parallel.
+    ///
+    /// The components are split into chunks that are processed on the current rayon
+    /// thread pool. Use [`ThreadPool::install`] to run on a particular pool.
+    ///
+    /// [`ThreadPool::install`]: rayon::ThreadPool::install
+    #[inline]
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = Mut<'_, C>> {
+        self.storage
+            .par_iter_mut_with_indices(self.tick)
+            .map(|(_, component)| component)
+    }
+
+    /// Iterate mutably over the components in parallel, along with the entities they
+    /// belong to.
+    #[inline]
+    pub fn par_iter_mut_with_ids(
+        &mut self,
+    ) -> impl ParallelIterator<Item = (EntityId, Mut<'_, C>)> {
+        let entities = self.entities;
+        self.storage
+            .par_iter_mut_with_indices(self.tick)
+            .filter_map(|(index, component)| Some((entities.alive_at(index)?, component)))
+    }
+
+    /// Call a function on every component in parallel.
+    #[inline]
+    pub fn par_for_each(&mut self, f: impl Fn(Mut<'_, C>) + Send + Sync) {
+        self.par_iter_mut().for_each(f);
+    }
+
+    /// Call a function on every component in parallel, along with the entity it belongs
+    /// to.
+    #[inline]
+    pub fn par_for_each_with_ids(&mut self, f: impl Fn(EntityId, Mut<'_, C>) + Send + Sync) {
+        self.par_iter_mut_with_ids()
+            .for_each(|(entity, component)| f(entity, component));
+    }
+}
+
+#[inline]
+fn iter_with_ids<'a, C: Component>(
+    storage: &'a ComponentStorage<C>,
+    entities: &'a EntityStorage,
+) -> impl Iterator<Item = (EntityId, &'a C)> {
+    storage
+        .iter_with_indices()
+        .filter_map(|(index, component)| Some((entities.alive_at(index)?, component)))
+}
+
+#[inline]
+fn iter_since<'a, C: Component>(
+    storage: &'a ComponentStorage<C>,
+    entities: &'a EntityStorage,
+    filter: impl Fn(ComponentTicks) -> bool + 'a,
+) -> impl Iterator<Item = (EntityId, &'a C)> {
+    storage
+        .iter_ticks_with_indices()
+        .filter(move |(_, _, ticks)| filter(*ticks))
+        .filter_map(|(index, component, _)| Some((entities.alive_at(index)?, component)))
+}
+
+/// A mutable reference to a component that marks it as changed when it is mutably
+/// dereferenced.
+pub struct Mut<'a, C> {
+    component: &'a mut C,
+    ticks: &'a mut ComponentTicks,
+    tick: Tick,
+}
+
+impl<'a, C> Mut<'a, C> {
+    #[inline]
+    pub(crate) fn new(component: &'a mut C, ticks: &'a mut ComponentTicks, tick: Tick) -> Self {
+        Self {
+            component,
+            ticks,
+            tick,
+        }
+    }
+
+    /// Get the underlying mutable reference, marking the component as changed.
+    #[inline]
+    pub fn into_inner(self) -> &'a mut C {
+        self.ticks.changed = self.tick;
+        self.component
+    }
+}
+
+impl<C> Deref for Mut<'_, C> {
+    type Target = C;
+
+    #[inline]
+    fn deref(&self) -> &C {
+        self.component
+    }
+}
+
+impl<C> DerefMut for Mut<'_, C> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut C {
+        self.ticks.changed = self.tick;
+        self.component
+    }
+}
+
+impl<C: std::fmt::Debug> std::fmt::Debug for Mut<'_, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.component.fmt(f)
     }
 }
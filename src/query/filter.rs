@@ -0,0 +1,161 @@
+use atomic_refcell::AtomicRef;
+
+use crate::sparse::SparseSet;
+use crate::storage::component::{Component, ComponentStorage, TrackedComponent};
+use crate::storage::entities::{EntityId, EntityStorage};
+use crate::world::{World, WorldData};
+
+use super::access::Access;
+use super::join::fetch::Fetch;
+use super::join::Joinable;
+use super::{Query, QueryFamily, QueryResult};
+
+/// A filter that restricts a join to entities that have a component, without fetching it.
+///
+/// For example, `(&mut positions, &with_enemy).join()` yields
+/// `(EntityId, Mut<Position>, ())` for every entity with a position and an `Enemy`.
+pub struct With<'a, C: Component> {
+    storage: AtomicRef<'a, ComponentStorage<C>>,
+    entities: &'a EntityStorage,
+}
+
+/// A filter that restricts a join to entities that don't have a component.
+///
+/// This can't drive a join, so it should be joined with at least one component query.
+pub struct Without<'a, C: Component> {
+    storage: AtomicRef<'a, ComponentStorage<C>>,
+    entities: &'a EntityStorage,
+}
+
+macro_rules! impl_filter {
+    ($filter:ident) => {
+        impl<'a, C: Component, D: WorldData> Query<'a, D> for $filter<'a, C> {
+            #[inline]
+            fn borrow(world: &'a World<D>) -> QueryResult<Self> {
+                let storage = world.all_storages.components.borrow_ref_or_insert()?;
+                let entities = &world.all_storages.entities;
+                Ok($filter { storage, entities })
+            }
+
+            #[inline]
+            fn access(access: &mut Access) {
+                access.read_component::<C>();
+            }
+        }
+
+        impl<C: Component, D: WorldData> QueryFamily<D> for $filter<'_, C> {
+            type Item<'a> = $filter<'a, C>;
+        }
+    };
+}
+
+impl_filter!(With);
+impl_filter!(Without);
+
+impl<C: Component> With<'_, C> {
+    /// Check whether an entity matches the filter.
+    #[inline]
+    pub fn matches(&self, entity: EntityId) -> bool {
+        self.entities.is_alive(entity) && self.storage.set.contains(entity.index())
+    }
+}
+
+impl<C: Component> Without<'_, C> {
+    /// Check whether an entity matches the filter.
+    #[inline]
+    pub fn matches(&self, entity: EntityId) -> bool {
+        self.entities.is_alive(entity) && !self.storage.set.contains(entity.index())
+    }
+}
+
+impl<'a, C: Component> Joinable<'a> for &'a With<'_, C> {
+    type Fetch = FetchWith<'a, C>;
+
+    #[inline]
+    fn into_fetch(self) -> Self::Fetch {
+        FetchWith {
+            set: &self.storage.set,
+            entities: self.entities,
+        }
+    }
+}
+
+impl<'a, C: Component> Joinable<'a> for &'a Without<'_, C> {
+    type Fetch = FetchWithout<'a, C>;
+
+    #[inline]
+    fn into_fetch(self) -> Self::Fetch {
+        FetchWithout {
+            set: &self.storage.set,
+            entities: self.entities,
+        }
+    }
+}
+
+pub struct FetchWith<'a, C> {
+    set: &'a SparseSet<TrackedComponent<C>>,
+    entities: &'a EntityStorage,
+}
+
+pub struct FetchWithout<'a, C> {
+    set: &'a SparseSet<TrackedComponent<C>>,
+    entities: &'a EntityStorage,
+}
+
+impl<'a, C: Component> Fetch<'a> for FetchWith<'a, C> {
+    type Item = ();
+
+    #[inline]
+    fn entities(&self) -> &'a EntityStorage {
+        self.entities
+    }
+
+    #[inline]
+    fn len(&self) -> Option<usize> {
+        Some(self.set.len())
+    }
+
+    #[inline]
+    fn sparse_index_at(&self, dense_index: usize) -> usize {
+        self.set.sparse_index_at(dense_index)
+    }
+
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.set.contains(index)
+    }
+
+    #[inline]
+    unsafe fn fetch(&self, index: usize) -> Option<Self::Item> {
+        self.set.contains(index).then_some(())
+    }
+}
+
+impl<'a, C: Component> Fetch<'a> for FetchWithout<'a, C> {
+    type Item = ();
+
+    #[inline]
+    fn entities(&self) -> &'a EntityStorage {
+        self.entities
+    }
+
+    #[inline]
+    fn len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn sparse_index_at(&self, _dense_index: usize) -> usize {
+        unreachable!("a `Without` filter can't drive a join")
+    }
+
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        !self.set.contains(index)
+    }
+
+    #[inline]
+    unsafe fn fetch(&self, index: usize) -> Option<Self::Item> {
+        (!self.set.contains(index)).then_some(())
+    }
+}
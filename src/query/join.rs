@@ -0,0 +1,480 @@
+use rayon::iter::plumbing::UnindexedConsumer;
+use rayon::prelude::*;
+
+use crate::sparse::{SparseSet, SparseSetRawMut};
+use crate::storage::component::{Component, TrackedComponent};
+use crate::storage::entities::{EntityId, EntityStorage};
+use crate::storage::tick::Tick;
+
+use self::fetch::{Fetch, FetchAll};
+use super::component::{Mut, QueryComp, QueryCompMut};
+
+/// A component query that can take part in a join.
+///
+/// This is implemented for shared references to [`QueryComp`] and [`QueryCompMut`], which
+/// fetch shared references to components, and for mutable references to [`QueryCompMut`],
+/// which fetch components as [`Mut`].
+///
+/// Shared references to the [`With`] and [`Without`] filters restrict a join to entities
+/// that do or don't have a component, and fetch `()`. Wrapping any of these in
+/// [`Optional`] fetches an [`Option`] instead of skipping entities that don't match.
+///
+/// [`With`]: super::filter::With
+/// [`Mut`]: super::component::Mut
+/// [`Without`]: super::filter::Without
+pub trait Joinable<'a> {
+    type Fetch: Fetch<'a>;
+
+    #[doc(hidden)]
+    fn into_fetch(self) -> Self::Fetch;
+}
+
+impl<'a, C: Component> Joinable<'a> for &'a QueryComp<'_, C> {
+    type Fetch = FetchComp<'a, C>;
+
+    #[inline]
+    fn into_fetch(self) -> Self::Fetch {
+        FetchComp {
+            set: &self.storage.set,
+            entities: self.entities,
+        }
+    }
+}
+
+impl<'a, C: Component> Joinable<'a> for &'a QueryCompMut<'_, C> {
+    type Fetch = FetchComp<'a, C>;
+
+    #[inline]
+    fn into_fetch(self) -> Self::Fetch {
+        FetchComp {
+            set: &self.storage.set,
+            entities: self.entities,
+        }
+    }
+}
+
+impl<'a, C: Component> Joinable<'a> for &'a mut QueryCompMut<'_, C> {
+    type Fetch = FetchCompMut<'a, C>;
+
+    #[inline]
+    fn into_fetch(self) -> Self::Fetch {
+        FetchCompMut {
+            set: self.storage.raw_mut(),
+            entities: self.entities,
+            tick: self.tick,
+        }
+    }
+}
+
+/// Iterate over every entity that has all of the components in a tuple of component
+/// queries.
+///
+/// Iteration is driven by the smallest storage, and the other storages are probed for
+/// each entity in it. Items are tuples of the entity id followed by one component from
+/// each query, for example `(&mut positions, &velocities).join()` yields
+/// `(EntityId, Mut<Position>, &Velocity)`. [`Mut`] marks the component as changed when
+/// it's mutably dereferenced.
+///
+/// [`Without`] filters and [`Optional`] members can't drive a join, so if every member is
+/// one of those, every entity is visited.
+///
+/// [`Mut`]: super::component::Mut
+/// [`Without`]: super::filter::Without
+pub trait Join<'a> {
+    type Fetch: FetchAll<'a>;
+
+    fn join(self) -> JoinIter<'a, Self::Fetch>;
+}
+
+/// Parallel iteration over a join.
+///
+/// The driving storage's dense array (or the range of entity indices, if no member can
+/// drive the join) is split into chunks that are processed on the current rayon thread
+/// pool, as with [`QueryCompMut::par_iter_mut`].
+pub trait ParJoin<'a>: Join<'a> {
+    fn par_join(self) -> ParJoinIter<'a, Self::Fetch>;
+}
+
+impl<'a, J: Join<'a>> ParJoin<'a> for J
+where
+    J::Fetch: Send + Sync,
+    <J::Fetch as FetchAll<'a>>::Item: Send,
+{
+    #[inline]
+    fn par_join(self) -> ParJoinIter<'a, Self::Fetch> {
+        let JoinIter {
+            fetch,
+            entities,
+            driver,
+            len,
+            ..
+        } = self.join();
+
+        ParJoinIter {
+            fetch,
+            entities,
+            driver,
+            len,
+        }
+    }
+}
+
+/// Fetch components from a joinable query if they exist, rather than skipping entities
+/// that don't have them.
+///
+/// For example, `(&positions, Optional(&velocities)).join()` yields
+/// `(EntityId, &Position, Option<&Velocity>)` for every entity with a position.
+pub struct Optional<J>(pub J);
+
+impl<'a, J: Joinable<'a>> Joinable<'a> for Optional<J> {
+    type Fetch = FetchOptional<J::Fetch>;
+
+    #[inline]
+    fn into_fetch(self) -> Self::Fetch {
+        FetchOptional(self.0.into_fetch())
+    }
+}
+
+/// An iterator over the entities that have all the components in a join.
+pub struct JoinIter<'a, F> {
+    fetch: F,
+    entities: &'a EntityStorage,
+
+    /// The member whose storage is iterated over, or `None` to iterate over every entity.
+    driver: Option<usize>,
+
+    dense_index: usize,
+    len: usize,
+}
+
+impl<'a, F: FetchAll<'a>> JoinIter<'a, F> {
+    #[inline]
+    fn new(fetch: F) -> Self {
+        let entities = fetch.entities();
+        let (driver, len) = match fetch.driver() {
+            Some((driver, len)) => (Some(driver), len),
+            None => (None, entities.index_bound()),
+        };
+
+        JoinIter {
+            fetch,
+            entities,
+            driver,
+            dense_index: 0,
+            len,
+        }
+    }
+}
+
+impl<'a, F: FetchAll<'a>> Iterator for JoinIter<'a, F> {
+    type Item = F::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.dense_index < self.len {
+            let index = match self.driver {
+                Some(driver) => self.fetch.sparse_index_at(driver, self.dense_index),
+                None => self.dense_index,
+            };
+            self.dense_index += 1;
+
+            if !self.fetch.contains(index) {
+                continue;
+            }
+
+            let Some(entity) = self.entities.alive_at(index) else {
+                continue;
+            };
+
+            // Each position in the driving storage (or each entity) has a different
+            // index, so no component is fetched more than once.
+            return Some(unsafe { self.fetch.fetch(entity, index) });
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len - self.dense_index))
+    }
+}
+
+/// A parallel iterator over the entities that have all the components in a join.
+pub struct ParJoinIter<'a, F> {
+    fetch: F,
+    entities: &'a EntityStorage,
+    driver: Option<usize>,
+    len: usize,
+}
+
+impl<'a, F> ParallelIterator for ParJoinIter<'a, F>
+where
+    F: FetchAll<'a> + Send + Sync,
+    F::Item: Send,
+{
+    type Item = F::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let Self {
+            fetch,
+            entities,
+            driver,
+            len,
+        } = self;
+
+        (0..len)
+            .into_par_iter()
+            .filter_map(|dense_index| {
+                let index = match driver {
+                    Some(driver) => fetch.sparse_index_at(driver, dense_index),
+                    None => dense_index,
+                };
+
+                if !fetch.contains(index) {
+                    return None;
+                }
+                let entity = entities.alive_at(index)?;
+
+                // As in `JoinIter`, each dense position has a different index, and each is
+                // visited once, so no component is fetched more than once.
+                Some(unsafe { fetch.fetch(entity, index) })
+            })
+            .drive_unindexed(consumer)
+    }
+}
+
+pub struct FetchComp<'a, C> {
+    set: &'a SparseSet<TrackedComponent<C>>,
+    entities: &'a EntityStorage,
+}
+
+pub struct FetchCompMut<'a, C> {
+    set: SparseSetRawMut<'a, TrackedComponent<C>>,
+    entities: &'a EntityStorage,
+    tick: Tick,
+}
+
+pub struct FetchOptional<F>(F);
+
+pub(super) mod fetch {
+    use crate::storage::entities::{EntityId, EntityStorage};
+
+    pub trait Fetch<'a> {
+        type Item;
+
+        fn entities(&self) -> &'a EntityStorage;
+
+        /// The number of indices this fetch contains, or `None` if it can't drive a join
+        /// because it may contain indices that aren't in its storage.
+        fn len(&self) -> Option<usize>;
+
+        fn sparse_index_at(&self, dense_index: usize) -> usize;
+
+        fn contains(&self, index: usize) -> bool;
+
+        /// # Safety
+        ///
+        /// A mutable fetch must not fetch the same index twice while the first
+        /// result is alive.
+        unsafe fn fetch(&self, index: usize) -> Option<Self::Item>;
+    }
+
+    pub trait FetchAll<'a> {
+        type Item;
+
+        fn entities(&self) -> &'a EntityStorage;
+
+        /// Get the member with the fewest components, and its length, if any member can
+        /// drive the join.
+        fn driver(&self) -> Option<(usize, usize)>;
+
+        fn sparse_index_at(&self, member: usize, dense_index: usize) -> usize;
+
+        fn contains(&self, index: usize) -> bool;
+
+        /// # Safety
+        ///
+        /// The same index must not be fetched twice while the first result is alive,
+        /// and every member must contain the index.
+        unsafe fn fetch(&self, entity: EntityId, index: usize) -> Self::Item;
+    }
+}
+
+impl<'a, C: Component> Fetch<'a> for FetchComp<'a, C> {
+    type Item = &'a C;
+
+    #[inline]
+    fn entities(&self) -> &'a EntityStorage {
+        self.entities
+    }
+
+    #[inline]
+    fn len(&self) -> Option<usize> {
+        Some(self.set.len())
+    }
+
+    #[inline]
+    fn sparse_index_at(&self, dense_index: usize) -> usize {
+        self.set.sparse_index_at(dense_index)
+    }
+
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.set.contains(index)
+    }
+
+    #[inline]
+    unsafe fn fetch(&self, index: usize) -> Option<Self::Item> {
+        self.set.get(index).map(|tracked| &tracked.component)
+    }
+}
+
+impl<'a, C: Component> Fetch<'a> for FetchCompMut<'a, C> {
+    type Item = Mut<'a, C>;
+
+    #[inline]
+    fn entities(&self) -> &'a EntityStorage {
+        self.entities
+    }
+
+    #[inline]
+    fn len(&self) -> Option<usize> {
+        Some(self.set.len())
+    }
+
+    #[inline]
+    fn sparse_index_at(&self, dense_index: usize) -> usize {
+        self.set.sparse_index_at(dense_index)
+    }
+
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.set.contains(index)
+    }
+
+    #[inline]
+    unsafe fn fetch(&self, index: usize) -> Option<Self::Item> {
+        let tracked = self.set.get_mut(index)?;
+        Some(Mut::new(
+            &mut tracked.component,
+            &mut tracked.ticks,
+            self.tick,
+        ))
+    }
+}
+
+impl<'a, F: Fetch<'a>> Fetch<'a> for FetchOptional<F> {
+    type Item = Option<F::Item>;
+
+    #[inline]
+    fn entities(&self) -> &'a EntityStorage {
+        self.0.entities()
+    }
+
+    #[inline]
+    fn len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn sparse_index_at(&self, _dense_index: usize) -> usize {
+        unreachable!("an optional fetch can't drive a join")
+    }
+
+    #[inline]
+    fn contains(&self, _index: usize) -> bool {
+        true
+    }
+
+    #[inline]
+    unsafe fn fetch(&self, index: usize) -> Option<Self::Item> {
+        if self.0.contains(index) {
+            Some(self.0.fetch(index))
+        } else {
+            Some(None)
+        }
+    }
+}
+
+macro_rules! impl_join {
+    ($(($query:ident, $index:tt)),*) => {
+        impl<'a, $($query: Fetch<'a>),*> FetchAll<'a> for ($($query,)*) {
+            type Item = (EntityId, $($query::Item,)*);
+
+            #[inline]
+            fn entities(&self) -> &'a EntityStorage {
+                self.0.entities()
+            }
+
+            #[inline]
+            fn driver(&self) -> Option<(usize, usize)> {
+                let mut driver: Option<(usize, usize)> = None;
+                $(
+                    if let Some(len) = self.$index.len() {
+                        if driver.map_or(true, |(_, driver_len)| len < driver_len) {
+                            driver = Some(($index, len));
+                        }
+                    }
+                )*
+                driver
+            }
+
+            #[inline]
+            fn sparse_index_at(&self, member: usize, dense_index: usize) -> usize {
+                match member {
+                    $($index => self.$index.sparse_index_at(dense_index),)*
+                    _ => unreachable!(),
+                }
+            }
+
+            #[inline]
+            fn contains(&self, index: usize) -> bool {
+                $(self.$index.contains(index))&&*
+            }
+
+            #[inline]
+            unsafe fn fetch(&self, entity: EntityId, index: usize) -> Self::Item {
+                (entity, $(self.$index.fetch(index).unwrap(),)*)
+            }
+        }
+
+        impl<'a, $($query: Joinable<'a>),*> Join<'a> for ($($query,)*) {
+            type Fetch = ($($query::Fetch,)*);
+
+            #[inline]
+            fn join(self) -> JoinIter<'a, Self::Fetch> {
+                JoinIter::new(($(self.$index.into_fetch(),)*))
+            }
+        }
+    };
+}
+
+impl_join!((Q0, 0));
+impl_join!((Q0, 0), (Q1, 1));
+impl_join!((Q0, 0), (Q1, 1), (Q2, 2));
+impl_join!((Q0, 0), (Q1, 1), (Q2, 2), (Q3, 3));
+impl_join!((Q0, 0), (Q1, 1), (Q2, 2), (Q3, 3), (Q4, 4));
+impl_join!((Q0, 0), (Q1, 1), (Q2, 2), (Q3, 3), (Q4, 4), (Q5, 5));
+impl_join!(
+    (Q0, 0),
+    (Q1, 1),
+    (Q2, 2),
+    (Q3, 3),
+    (Q4, 4),
+    (Q5, 5),
+    (Q6, 6)
+);
+impl_join!(
+    (Q0, 0),
+    (Q1, 1),
+    (Q2, 2),
+    (Q3, 3),
+    (Q4, 4),
+    (Q5, 5),
+    (Q6, 6),
+    (Q7, 7)
+);
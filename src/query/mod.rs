@@ -1,10 +1,39 @@
 use crate::{prelude::World, world::WorldData};
 
+use self::access::Access;
+
+pub use atomic_refcell::{AtomicRef, AtomicRefMut, BorrowError, BorrowMutError};
+
+pub mod access;
+pub mod commands;
 pub mod component;
+pub mod filter;
+pub mod join;
+pub mod removed;
 pub mod unique;
 
 pub trait Query<'a, D: WorldData>: Sized {
     fn borrow(world: &'a World<D>) -> QueryResult<Self>;
+
+    /// Add the borrows this query makes to `access`.
+    ///
+    /// Schedules use this to reject conflicting systems when they're added, and to decide
+    /// which systems can run in parallel, so every borrow made in
+    /// [`borrow`](Self::borrow) must be declared. Composed queries can delegate to the
+    /// queries they're made of.
+    fn access(access: &mut Access);
+}
+
+/// A query type that can be borrowed for any lifetime.
+///
+/// Systems that are stored (for example in a [`Schedule`]) are run against many
+/// different borrows of the world, so each of their queries needs to be nameable without a
+/// fixed lifetime. `Item<'a>` is the same query borrowed for `'a`. Borrowed queries must
+/// be [`Send`] so that systems can be run on other threads.
+///
+/// [`Schedule`]: crate::schedule::Schedule
+pub trait QueryFamily<D: WorldData> {
+    type Item<'a>: Query<'a, D> + Send;
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -13,10 +42,10 @@ pub enum QueryError {
     StorageMissing,
 
     #[error("{0}")]
-    BorrowError(#[from] std::cell::BorrowError),
+    BorrowError(BorrowError),
 
     #[error("{0}")]
-    BorrowMutError(#[from] std::cell::BorrowMutError),
+    BorrowMutError(BorrowMutError),
 
     #[error("entity is dead")]
     EntityDead,
@@ -25,4 +54,51 @@ pub enum QueryError {
     EntityMissing,
 }
 
+// The borrow errors don't implement `std::error::Error`, so they can't be sources.
+impl From<BorrowError> for QueryError {
+    #[inline]
+    fn from(error: BorrowError) -> Self {
+        Self::BorrowError(error)
+    }
+}
+
+impl From<BorrowMutError> for QueryError {
+    #[inline]
+    fn from(error: BorrowMutError) -> Self {
+        Self::BorrowMutError(error)
+    }
+}
+
 pub type QueryResult<T> = Result<T, QueryError>;
+
+macro_rules! impl_query {
+    ($($query:ident),*) => {
+        impl<'a, D: WorldData, $($query: Query<'a, D>),*> Query<'a, D> for ($($query,)*) {
+            #[allow(unused_variables)]
+            #[inline]
+            fn borrow(world: &'a World<D>) -> QueryResult<Self> {
+                Ok(($($query::borrow(world)?,)*))
+            }
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn access(access: &mut Access) {
+                $($query::access(access);)*
+            }
+        }
+
+        impl<D: WorldData, $($query: QueryFamily<D>),*> QueryFamily<D> for ($($query,)*) {
+            type Item<'a> = ($($query::Item<'a>,)*);
+        }
+    }
+}
+
+impl_query!();
+impl_query!(Q0);
+impl_query!(Q0, Q1);
+impl_query!(Q0, Q1, Q2);
+impl_query!(Q0, Q1, Q2, Q3);
+impl_query!(Q0, Q1, Q2, Q3, Q4);
+impl_query!(Q0, Q1, Q2, Q3, Q4, Q5);
+impl_query!(Q0, Q1, Q2, Q3, Q4, Q5, Q6);
+impl_query!(Q0, Q1, Q2, Q3, Q4, Q5, Q6, Q7);
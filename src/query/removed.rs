@@ -0,0 +1,83 @@
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+use crate::query::access::Access;
+use crate::query::{Query, QueryFamily, QueryResult};
+use crate::storage::component::{Component, RemovalLog};
+use crate::storage::entities::EntityId;
+use crate::world::{World, WorldData};
+
+/// A query for the entities that have had a component removed, either directly or by
+/// being despawned.
+///
+/// Each reader keeps its own [`RemovedCursor`], so several systems can see the same
+/// removals. Removals are kept until [`World::clear_removed_components`] is called.
+///
+/// The removal log is kept apart from the components, so this doesn't borrow the
+/// component storage, and can be used alongside a [`QueryCompMut`] of the same
+/// component. Removals made while the log is being read are seen by the next read.
+///
+/// [`QueryCompMut`]: crate::query::component::QueryCompMut
+pub struct RemovedComponents<'a, C: Component> {
+    log: &'a Mutex<RemovalLog>,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<'a, C: Component, D: WorldData> Query<'a, D> for RemovedComponents<'a, C> {
+    #[inline]
+    fn borrow(world: &'a World<D>) -> QueryResult<Self> {
+        Ok(RemovedComponents {
+            log: world.all_storages.removed.get::<C>(),
+            _marker: PhantomData,
+        })
+    }
+
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read_removed::<C>();
+    }
+}
+
+impl<C: Component, D: WorldData> QueryFamily<D> for RemovedComponents<'_, C> {
+    type Item<'a> = RemovedComponents<'a, C>;
+}
+
+impl<C: Component> RemovedComponents<'_, C> {
+    /// Iterate over the removals this cursor hasn't seen yet, and mark them as seen.
+    #[inline]
+    pub fn read(&self, cursor: &mut RemovedCursor<C>) -> impl Iterator<Item = EntityId> {
+        RemovalLog::lock(self.log)
+            .read(&mut cursor.position)
+            .to_vec()
+            .into_iter()
+    }
+
+    /// Iterate over every removal that hasn't been cleared.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = EntityId> {
+        RemovalLog::lock(self.log).entities().to_vec().into_iter()
+    }
+}
+
+/// A reader's position in the removal log of a component.
+pub struct RemovedCursor<C: Component> {
+    position: usize,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C: Component> Default for RemovedCursor<C> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            position: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C: Component> RemovedCursor<C> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
@@ -1,12 +1,13 @@
-use std::cell::{Ref, RefMut};
+use atomic_refcell::{AtomicRef, AtomicRefMut};
 
 use crate::prelude::World;
-use crate::query::{Query, QueryResult};
+use crate::query::access::Access;
+use crate::query::{Query, QueryError, QueryFamily, QueryResult};
 use crate::storage::unique::{Unique, UniqueStorage};
 use crate::world::WorldData;
 
 pub struct QueryUnique<'a, T: Unique> {
-    storage: Ref<'a, UniqueStorage<T>>,
+    storage: AtomicRef<'a, UniqueStorage<T>>,
 }
 
 impl<'a, T: Unique, D: WorldData> Query<'a, D> for QueryUnique<'a, T> {
@@ -15,10 +16,19 @@ impl<'a, T: Unique, D: WorldData> Query<'a, D> for QueryUnique<'a, T> {
         let storage = world.all_storages.uniques.borrow_ref()?;
         Ok(QueryUnique { storage })
     }
+
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read_unique::<T>();
+    }
+}
+
+impl<T: Unique, D: WorldData> QueryFamily<D> for QueryUnique<'_, T> {
+    type Item<'a> = QueryUnique<'a, T>;
 }
 
 pub struct QueryUniqueMut<'a, T: Unique> {
-    storage: RefMut<'a, UniqueStorage<T>>,
+    storage: AtomicRefMut<'a, UniqueStorage<T>>,
 }
 
 impl<'a, T: Unique, D: WorldData> Query<'a, D> for QueryUniqueMut<'a, T> {
@@ -27,6 +37,50 @@ impl<'a, T: Unique, D: WorldData> Query<'a, D> for QueryUniqueMut<'a, T> {
         let storage = world.all_storages.uniques.borrow_mut()?;
         Ok(QueryUniqueMut { storage })
     }
+
+    #[inline]
+    fn access(access: &mut Access) {
+        access.write_unique::<T>();
+    }
+}
+
+impl<T: Unique, D: WorldData> QueryFamily<D> for QueryUniqueMut<'_, T> {
+    type Item<'a> = QueryUniqueMut<'a, T>;
+}
+
+/// Shared access to a unique that may not exist.
+///
+/// Unlike [`QueryUnique`], borrowing this doesn't fail if the unique is missing.
+pub struct OptionalUnique<'a, T: Unique> {
+    storage: Option<AtomicRef<'a, UniqueStorage<T>>>,
+}
+
+impl<'a, T: Unique, D: WorldData> Query<'a, D> for OptionalUnique<'a, T> {
+    #[inline]
+    fn borrow(world: &'a World<D>) -> QueryResult<Self> {
+        let storage = match world.all_storages.uniques.borrow_ref() {
+            Ok(storage) => Some(storage),
+            Err(QueryError::StorageMissing) => None,
+            Err(error) => return Err(error),
+        };
+        Ok(OptionalUnique { storage })
+    }
+
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read_unique::<T>();
+    }
+}
+
+impl<T: Unique, D: WorldData> QueryFamily<D> for OptionalUnique<'_, T> {
+    type Item<'a> = OptionalUnique<'a, T>;
+}
+
+impl<T: Unique> OptionalUnique<'_, T> {
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.storage.as_ref().map(|storage| &storage.0)
+    }
 }
 
 impl<T: Unique> QueryUnique<'_, T> {
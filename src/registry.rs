@@ -0,0 +1,198 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+use crate::delta::ComponentDiff;
+use crate::snapshot::{ComponentClone, UniqueClone};
+use crate::storage::component::Component;
+use crate::storage::unique::Unique;
+
+#[cfg(feature = "serde")]
+use crate::serialize::{ComponentSerde, UniqueSerde};
+
+/// The component and unique types that have been registered with a world, each under a
+/// stable name, along with what can be done with them.
+#[derive(Default)]
+pub(crate) struct Registry {
+    pub components: Vec<ComponentRegistration>,
+    pub uniques: Vec<UniqueRegistration>,
+}
+
+pub(crate) struct ComponentRegistration {
+    pub name: &'static str,
+    pub type_id: TypeId,
+    pub clone: Option<ComponentClone>,
+    pub diff: Option<ComponentDiff>,
+
+    #[cfg(feature = "serde")]
+    pub serde: Option<ComponentSerde>,
+}
+
+pub(crate) struct UniqueRegistration {
+    pub name: &'static str,
+    pub type_id: TypeId,
+    pub clone: Option<UniqueClone>,
+
+    #[cfg(feature = "serde")]
+    pub serde: Option<UniqueSerde>,
+}
+
+impl Registry {
+    pub fn register_component<C: Component>(
+        &mut self,
+        name: &'static str,
+    ) -> ComponentConfig<'_, C> {
+        let index = register(
+            &mut self.components,
+            name,
+            TypeId::of::<C>(),
+            |registration| (registration.name, registration.type_id),
+            || ComponentRegistration {
+                name,
+                type_id: TypeId::of::<C>(),
+                clone: None,
+                diff: None,
+
+                #[cfg(feature = "serde")]
+                serde: None,
+            },
+        );
+
+        ComponentConfig {
+            registration: &mut self.components[index],
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn register_unique<T: Unique>(&mut self, name: &'static str) -> UniqueConfig<'_, T> {
+        let index = register(
+            &mut self.uniques,
+            name,
+            TypeId::of::<T>(),
+            |registration| (registration.name, registration.type_id),
+            || UniqueRegistration {
+                name,
+                type_id: TypeId::of::<T>(),
+                clone: None,
+
+                #[cfg(feature = "serde")]
+                serde: None,
+            },
+        );
+
+        UniqueConfig {
+            registration: &mut self.uniques[index],
+            _marker: PhantomData,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn component(&self, name: &str) -> Option<&ComponentRegistration> {
+        self.components
+            .iter()
+            .find(|registration| registration.name == name)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn unique(&self, name: &str) -> Option<&UniqueRegistration> {
+        self.uniques
+            .iter()
+            .find(|registration| registration.name == name)
+    }
+}
+
+/// Find an existing registration for a type, or add a new one, returning its index.
+fn register<R>(
+    registrations: &mut Vec<R>,
+    name: &'static str,
+    type_id: TypeId,
+    key: impl Fn(&R) -> (&'static str, TypeId),
+    new: impl FnOnce() -> R,
+) -> usize {
+    let existing = registrations.iter().position(|registration| {
+        let (existing_name, existing_type_id) = key(registration);
+        existing_name == name || existing_type_id == type_id
+    });
+
+    match existing {
+        Some(index) => {
+            assert!(
+                key(&registrations[index]) == (name, type_id),
+                "`{name}` is already registered, or the type is registered under another name",
+            );
+            index
+        }
+        None => {
+            registrations.push(new());
+            registrations.len() - 1
+        }
+    }
+}
+
+/// Configures what can be done with a component type that was just registered.
+pub struct ComponentConfig<'r, C: Component> {
+    registration: &'r mut ComponentRegistration,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C: Component> ComponentConfig<'_, C> {
+    /// Include this component in [snapshots](crate::snapshot::Snapshot).
+    #[inline]
+    pub fn cloneable(self) -> Self
+    where
+        C: Clone,
+    {
+        self.registration.clone = Some(ComponentClone::of::<C>());
+        self
+    }
+
+    /// Include this component in snapshots, and in the [deltas](crate::delta::Delta)
+    /// between them.
+    #[inline]
+    pub fn diffable(self) -> Self
+    where
+        C: Clone + PartialEq,
+    {
+        self.registration.diff = Some(ComponentDiff::of::<C>());
+        self.cloneable()
+    }
+
+    /// Include this component when the world is serialized and deserialized.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn serde(self) -> Self
+    where
+        C: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.registration.serde = Some(ComponentSerde::of::<C>());
+        self
+    }
+}
+
+/// Configures what can be done with a unique type that was just registered.
+pub struct UniqueConfig<'r, T: Unique> {
+    registration: &'r mut UniqueRegistration,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Unique> UniqueConfig<'_, T> {
+    /// Include this unique in [snapshots](crate::snapshot::Snapshot).
+    #[inline]
+    pub fn cloneable(self) -> Self
+    where
+        T: Clone,
+    {
+        self.registration.clone = Some(UniqueClone::of::<T>());
+        self
+    }
+
+    /// Include this unique when the world is serialized and deserialized.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn serde(self) -> Self
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.registration.serde = Some(UniqueSerde::of::<T>());
+        self
+    }
+}
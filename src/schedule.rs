@@ -0,0 +1,302 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::query::access::AccessConflict;
+use crate::query::QueryError;
+use crate::system::{BoxedSystem, IntoBoxedSystem};
+use crate::world::{World, WorldData};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("system `{name}` failed: {error}")]
+    System {
+        name: &'static str,
+        #[source]
+        error: QueryError,
+    },
+
+    #[error("system `{name}` has {error}")]
+    Conflict {
+        name: &'static str,
+        #[source]
+        error: AccessConflict,
+    },
+
+    #[error("ordering constraints between systems form a cycle")]
+    Cycle,
+
+    #[error("an ordering constraint refers to a system that isn't in the schedule")]
+    UnknownSystem,
+}
+
+pub type ScheduleResult<T> = Result<T, ScheduleError>;
+
+/// An identifier for a system in a [`Schedule`], used to order other systems around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SystemId(usize);
+
+/// A list of systems that can be run against a world any number of times.
+///
+/// Systems run in the order they were added, unless [`before`] or [`after`] constraints
+/// say otherwise.
+///
+/// [`before`]: SystemConfig::before
+/// [`after`]: SystemConfig::after
+pub struct Schedule<D: WorldData = ()> {
+    systems: Vec<BoxedSystem<D>>,
+
+    /// Pairs of systems where the first must run before the second.
+    constraints: Vec<(usize, usize)>,
+
+    /// The order to run the systems in, computed when the schedule is first run.
+    order: Option<Vec<usize>>,
+}
+
+impl<D: WorldData> Default for Schedule<D> {
+    fn default() -> Self {
+        Self {
+            systems: vec![],
+            constraints: vec![],
+            order: None,
+        }
+    }
+}
+
+impl<D: WorldData> Schedule<D> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a system, returning a [`SystemConfig`] that can be used to order it.
+    ///
+    /// Fails if the system's queries conflict with each other, since such a system could
+    /// never run successfully.
+    pub fn add_system<S, Input, Output>(&mut self, system: S) -> ScheduleResult<SystemConfig<'_, D>>
+    where
+        S: IntoBoxedSystem<D, Input, Output>,
+    {
+        let system = system.into_boxed();
+        system
+            .access()
+            .validate()
+            .map_err(|error| ScheduleError::Conflict {
+                name: system.name(),
+                error,
+            })?;
+
+        let id = SystemId(self.systems.len());
+        self.systems.push(system);
+        self.order = None;
+        Ok(SystemConfig { schedule: self, id })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    /// Run every system once, advancing the change tick after each one.
+    ///
+    /// Stops at the first system that fails to borrow its queries.
+    pub fn run(&mut self, world: &World<D>) -> ScheduleResult<()> {
+        if self.order.is_none() {
+            self.order = Some(self.sort()?);
+        }
+
+        let order = self.order.as_deref().unwrap_or_default();
+        for &index in order {
+            let system = &self.systems[index];
+            let result = system.run(world);
+            world.advance_change_tick();
+
+            result.map_err(|error| ScheduleError::System {
+                name: system.name(),
+                error,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Run every system once, running systems whose access doesn't conflict on separate
+    /// threads.
+    ///
+    /// Systems that conflict with each other still run in the same order as with
+    /// [`run`](Self::run), so the outcome is the same. Each system's queries are borrowed on
+    /// the calling thread just before it starts, and the change tick is advanced after
+    /// each borrow.
+    ///
+    /// If a system fails to borrow its queries, no more systems are started, but the ones
+    /// that are already running are allowed to finish.
+    pub fn run_parallel(&mut self, world: &World<D>) -> ScheduleResult<()> {
+        if self.order.is_none() {
+            self.order = Some(self.sort()?);
+        }
+
+        let order = self.order.as_deref().unwrap_or_default();
+        let systems = &self.systems;
+
+        // Each system waits for the earlier systems it's constrained by or conflicts with.
+        let mut dependents = vec![vec![]; systems.len()];
+        let mut waiting_on = vec![0; systems.len()];
+        for (position, &index) in order.iter().enumerate() {
+            for &earlier in &order[..position] {
+                if self.constraints.contains(&(earlier, index))
+                    || systems[earlier]
+                        .access()
+                        .conflicts_with(systems[index].access())
+                {
+                    dependents[earlier].push(index);
+                    waiting_on[index] += 1;
+                }
+            }
+        }
+
+        let mut result = Ok(());
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let mut started = vec![false; systems.len()];
+            let mut running = 0;
+
+            loop {
+                for &index in order {
+                    if result.is_err() {
+                        break;
+                    }
+
+                    if started[index] || waiting_on[index] > 0 {
+                        continue;
+                    }
+                    started[index] = true;
+
+                    let system = &systems[index];
+                    let task = system.borrow(world);
+                    world.advance_change_tick();
+
+                    match task {
+                        Ok(task) => {
+                            let finished = Finished {
+                                sender: sender.clone(),
+                                index,
+                            };
+                            scope.spawn(move || {
+                                let _finished = finished;
+                                task();
+                            });
+                            running += 1;
+                        }
+                        Err(error) => {
+                            result = Err(ScheduleError::System {
+                                name: system.name(),
+                                error,
+                            });
+                        }
+                    }
+                }
+
+                if running == 0 {
+                    break;
+                }
+
+                // We hold a sender ourselves, so this can't fail.
+                let index = receiver.recv().unwrap();
+                running -= 1;
+
+                for &dependent in &dependents[index] {
+                    waiting_on[dependent] -= 1;
+                }
+            }
+        });
+
+        result
+    }
+
+    /// Sort the systems so that every constraint is satisfied, keeping insertion order
+    /// wherever the constraints allow it.
+    fn sort(&self) -> ScheduleResult<Vec<usize>> {
+        let mut in_degree = vec![0; self.systems.len()];
+        let mut successors = vec![vec![]; self.systems.len()];
+        for &(first, then) in &self.constraints {
+            if first >= self.systems.len() || then >= self.systems.len() {
+                return Err(ScheduleError::UnknownSystem);
+            }
+            successors[first].push(then);
+            in_degree[then] += 1;
+        }
+
+        let mut ready: BinaryHeap<_> = (0..self.systems.len())
+            .filter(|&index| in_degree[index] == 0)
+            .map(Reverse)
+            .collect();
+
+        let mut order = Vec::with_capacity(self.systems.len());
+        while let Some(Reverse(index)) = ready.pop() {
+            order.push(index);
+            for &then in &successors[index] {
+                in_degree[then] -= 1;
+                if in_degree[then] == 0 {
+                    ready.push(Reverse(then));
+                }
+            }
+        }
+
+        if order.len() != self.systems.len() {
+            return Err(ScheduleError::Cycle);
+        }
+
+        Ok(order)
+    }
+}
+
+/// Reports that a system has finished, even if it panicked.
+struct Finished {
+    sender: Sender<usize>,
+    index: usize,
+}
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        let _ = self.sender.send(self.index);
+    }
+}
+
+/// Configures the ordering of a system that was just added to a [`Schedule`].
+pub struct SystemConfig<'s, D: WorldData> {
+    schedule: &'s mut Schedule<D>,
+    id: SystemId,
+}
+
+impl<D: WorldData> SystemConfig<'_, D> {
+    /// Run this system before another one.
+    ///
+    /// `other` should be the id of a system in the same schedule. Running the schedule
+    /// fails with [`ScheduleError::UnknownSystem`] if there's no system with that id.
+    #[inline]
+    pub fn before(self, other: SystemId) -> Self {
+        self.schedule.constraints.push((self.id.0, other.0));
+        self
+    }
+
+    /// Run this system after another one.
+    ///
+    /// See [`before`](Self::before) for which ids are allowed.
+    #[inline]
+    pub fn after(self, other: SystemId) -> Self {
+        self.schedule.constraints.push((other.0, self.id.0));
+        self
+    }
+
+    #[inline]
+    pub fn id(self) -> SystemId {
+        self.id
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt;
+
+use atomic_refcell::AtomicRef;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::erased_storages::AllStorages;
+use crate::query::{QueryError, QueryResult};
+use crate::registry::Registry;
+use crate::storage::component::{Component, ComponentStorage};
+use crate::storage::entities::{EntityStorage, SavedEntities};
+use crate::storage::tick::Tick;
+use crate::storage::unique::{Unique, UniqueStorage};
+use crate::world::{World, WorldData};
+
+type SerializeFn =
+    for<'a> fn(&'a AllStorages) -> QueryResult<Option<Box<dyn erased_serde::Serialize + 'a>>>;
+
+type DeserializeFn<Args> = for<'de> fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+    &mut AllStorages,
+    Args,
+) -> Result<(), erased_serde::Error>;
+
+/// How to serialize and deserialize the storage of a registered component type.
+pub(crate) struct ComponentSerde {
+    serialize: SerializeFn,
+    deserialize: DeserializeFn<Tick>,
+}
+
+impl ComponentSerde {
+    pub fn of<C: Component + Serialize + DeserializeOwned>() -> Self {
+        Self {
+            serialize: serialize_components::<C>,
+            deserialize: deserialize_components::<C>,
+        }
+    }
+}
+
+/// How to serialize and deserialize a registered unique type.
+pub(crate) struct UniqueSerde {
+    serialize: SerializeFn,
+    deserialize: DeserializeFn<()>,
+}
+
+impl UniqueSerde {
+    pub fn of<T: Unique + Serialize + DeserializeOwned>() -> Self {
+        Self {
+            serialize: serialize_unique::<T>,
+            deserialize: deserialize_unique::<T>,
+        }
+    }
+}
+
+impl<Data: WorldData> World<Data> {
+    /// Serialize the entities, along with every component and unique whose type was
+    /// registered with [`serde`](crate::registry::ComponentConfig::serde).
+    ///
+    /// The entity allocator's state is saved exactly, so entity ids stay valid across a
+    /// round trip. Storages are keyed by their registered names. The world data and change
+    /// ticks aren't saved.
+    ///
+    /// Fails if a storage that needs to be saved is borrowed mutably.
+    pub fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut world = serializer.serialize_struct("World", 3)?;
+        world.serialize_field("entities", &self.all_storages.entities.save())?;
+        world.serialize_field(
+            "components",
+            &SerializeStorages {
+                registrations: self.registry.components.iter().filter_map(|registration| {
+                    Some((registration.name, registration.serde.as_ref()?.serialize))
+                }),
+                all_storages: &self.all_storages,
+            },
+        )?;
+        world.serialize_field(
+            "uniques",
+            &SerializeStorages {
+                registrations: self.registry.uniques.iter().filter_map(|registration| {
+                    Some((registration.name, registration.serde.as_ref()?.serialize))
+                }),
+                all_storages: &self.all_storages,
+            },
+        )?;
+        world.end()
+    }
+
+    /// Replace the entities and components with ones that were saved by
+    /// [`serialize`](Self::serialize).
+    ///
+    /// Components of types that weren't saved are removed, since the entities they belong
+    /// to are gone. Saved uniques replace existing ones, and other uniques are kept. If
+    /// deserialization fails, the world is left unchanged.
+    ///
+    /// Every saved component and unique type must have been registered with
+    /// [`serde`](crate::registry::ComponentConfig::serde) under the name it was saved
+    /// with.
+    pub fn deserialize<'de, De: Deserializer<'de>>(
+        &mut self,
+        deserializer: De,
+    ) -> Result<(), De::Error> {
+        let loaded = deserializer.deserialize_struct(
+            "World",
+            &["entities", "components", "uniques"],
+            WorldVisitor {
+                registry: &self.registry,
+                tick: self.change_tick(),
+            },
+        )?;
+
+        self.all_storages.entities = loaded.entities;
+        self.all_storages.components = loaded.components;
+        self.all_storages.uniques.append(loaded.uniques);
+
+        Ok(())
+    }
+}
+
+fn serialize_components<C: Component + Serialize>(
+    all_storages: &AllStorages,
+) -> QueryResult<Option<Box<dyn erased_serde::Serialize + '_>>> {
+    match all_storages.components.borrow_ref::<ComponentStorage<C>>() {
+        Ok(storage) => Ok(Some(Box::new(SerializeComponents {
+            storage,
+            entities: &all_storages.entities,
+        }))),
+        Err(QueryError::StorageMissing) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn deserialize_components<C: Component + DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    all_storages: &mut AllStorages,
+    tick: Tick,
+) -> Result<(), erased_serde::Error> {
+    let components: Vec<(u32, C)> = erased_serde::deserialize(deserializer)?;
+
+    let mut storage = ComponentStorage::<C>::default();
+    for (index, component) in components {
+        let index = index as usize;
+        if all_storages.entities.alive_at(index).is_none() {
+            return Err(de::Error::custom(format_args!(
+                "component belongs to dead entity {index}"
+            )));
+        }
+
+        if storage.insert(index, component, tick).is_some() {
+            return Err(de::Error::custom(format_args!(
+                "entity {index} has the same component twice"
+            )));
+        }
+    }
+
+    let _ = all_storages.components.insert(storage);
+    Ok(())
+}
+
+fn serialize_unique<T: Unique + Serialize>(
+    all_storages: &AllStorages,
+) -> QueryResult<Option<Box<dyn erased_serde::Serialize + '_>>> {
+    match all_storages.uniques.borrow_ref::<UniqueStorage<T>>() {
+        Ok(storage) => Ok(Some(Box::new(SerializeUnique(storage)))),
+        Err(QueryError::StorageMissing) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn deserialize_unique<T: Unique + DeserializeOwned>(
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    all_storages: &mut AllStorages,
+    _: (),
+) -> Result<(), erased_serde::Error> {
+    let unique: T = erased_serde::deserialize(deserializer)?;
+    let _ = all_storages.uniques.insert(UniqueStorage(unique));
+    Ok(())
+}
+
+/// A map from registered names to storages.
+struct SerializeStorages<'a, I> {
+    registrations: I,
+    all_storages: &'a AllStorages,
+}
+
+impl<I> Serialize for SerializeStorages<'_, I>
+where
+    I: Iterator<Item = (&'static str, SerializeFn)> + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, serialize) in self.registrations.clone() {
+            let storage = serialize(self.all_storages).map_err(ser::Error::custom)?;
+            if let Some(storage) = storage {
+                map.serialize_entry(name, &*storage)?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// A list of `(index, component)` pairs.
+struct SerializeComponents<'a, C: Component> {
+    storage: AtomicRef<'a, ComponentStorage<C>>,
+    entities: &'a EntityStorage,
+}
+
+impl<C: Component + Serialize> Serialize for SerializeComponents<'_, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.storage
+                .iter_with_indices()
+                .filter(|&(index, _)| self.entities.alive_at(index).is_some())
+                .map(|(index, component)| (index as u32, component)),
+        )
+    }
+}
+
+struct SerializeUnique<'a, T: Unique>(AtomicRef<'a, UniqueStorage<T>>);
+
+impl<T: Unique + Serialize> Serialize for SerializeUnique<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0 .0.serialize(serializer)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    Entities,
+    Components,
+    Uniques,
+}
+
+/// Loads a world into fresh storages, so that nothing changes if loading fails.
+struct WorldVisitor<'r> {
+    registry: &'r Registry,
+    tick: Tick,
+}
+
+impl WorldVisitor<'_> {
+    fn load_entities<E: de::Error>(
+        all_storages: &mut AllStorages,
+        saved: SavedEntities,
+    ) -> Result<(), E> {
+        all_storages.entities = EntityStorage::load(saved).map_err(E::custom)?;
+        Ok(())
+    }
+}
+
+impl<'de> Visitor<'de> for WorldVisitor<'_> {
+    type Value = AllStorages;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a world")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut all_storages = AllStorages::default();
+
+        let entities = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        Self::load_entities(&mut all_storages, entities)?;
+
+        seq.next_element_seed(ComponentsSeed {
+            registry: self.registry,
+            all_storages: &mut all_storages,
+            tick: self.tick,
+        })?
+        .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        seq.next_element_seed(UniquesSeed {
+            registry: self.registry,
+            all_storages: &mut all_storages,
+        })?
+        .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+
+        Ok(all_storages)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut all_storages = AllStorages::default();
+        let (mut entities, mut components, mut uniques) = (false, false, false);
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Entities => {
+                    if entities {
+                        return Err(de::Error::duplicate_field("entities"));
+                    }
+                    Self::load_entities(&mut all_storages, map.next_value()?)?;
+                    entities = true;
+                }
+                Field::Components => {
+                    if components {
+                        return Err(de::Error::duplicate_field("components"));
+                    }
+                    if !entities {
+                        return Err(de::Error::custom(
+                            "`entities` must come before `components`",
+                        ));
+                    }
+                    map.next_value_seed(ComponentsSeed {
+                        registry: self.registry,
+                        all_storages: &mut all_storages,
+                        tick: self.tick,
+                    })?;
+                    components = true;
+                }
+                Field::Uniques => {
+                    if uniques {
+                        return Err(de::Error::duplicate_field("uniques"));
+                    }
+                    map.next_value_seed(UniquesSeed {
+                        registry: self.registry,
+                        all_storages: &mut all_storages,
+                    })?;
+                    uniques = true;
+                }
+            }
+        }
+
+        if !entities {
+            return Err(de::Error::missing_field("entities"));
+        }
+        if !components {
+            return Err(de::Error::missing_field("components"));
+        }
+        if !uniques {
+            return Err(de::Error::missing_field("uniques"));
+        }
+
+        Ok(all_storages)
+    }
+}
+
+struct ComponentsSeed<'a> {
+    registry: &'a Registry,
+    all_storages: &'a mut AllStorages,
+    tick: Tick,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of component storages")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(name) = map.next_key::<String>()? {
+            let serde = self
+                .registry
+                .component(&name)
+                .and_then(|registration| registration.serde.as_ref())
+                .ok_or_else(|| de::Error::custom(format_args!("unknown component `{name}`")))?;
+
+            map.next_value_seed(StorageSeed {
+                deserialize: serde.deserialize,
+                all_storages: &mut *self.all_storages,
+                args: self.tick,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+struct UniquesSeed<'a> {
+    registry: &'a Registry,
+    all_storages: &'a mut AllStorages,
+}
+
+impl<'de> DeserializeSeed<'de> for UniquesSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for UniquesSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of uniques")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(name) = map.next_key::<String>()? {
+            let serde = self
+                .registry
+                .unique(&name)
+                .and_then(|registration| registration.serde.as_ref())
+                .ok_or_else(|| de::Error::custom(format_args!("unknown unique `{name}`")))?;
+
+            map.next_value_seed(StorageSeed {
+                deserialize: serde.deserialize,
+                all_storages: &mut *self.all_storages,
+                args: (),
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes one storage with a registered function.
+struct StorageSeed<'a, Args> {
+    deserialize: DeserializeFn<Args>,
+    all_storages: &'a mut AllStorages,
+    args: Args,
+}
+
+impl<'de, Args> DeserializeSeed<'de> for StorageSeed<'_, Args> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.deserialize)(&mut deserializer, self.all_storages, self.args)
+            .map_err(de::Error::custom)
+    }
+}
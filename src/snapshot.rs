@@ -0,0 +1,326 @@
+use std::any::{Any, TypeId};
+use std::collections::VecDeque;
+
+use crate::delta::ComponentDiff;
+use crate::erased_storages::AllStorages;
+use crate::query::{QueryError, QueryResult};
+use crate::sparse::SparseSet;
+use crate::storage::component::{Component, ComponentStorage, TrackedComponent};
+use crate::storage::entities::{EntityId, EntityStorage};
+use crate::storage::unique::{Unique, UniqueStorage};
+use crate::world::{World, WorldData};
+
+type SnapshotFn = fn(&AllStorages) -> QueryResult<Option<Box<dyn Any + Send + Sync>>>;
+type RestoreFn = fn(&mut AllStorages, Option<&(dyn Any + Send + Sync)>);
+type CloneComponentFn = fn(&mut AllStorages, EntityId, EntityId);
+
+/// How to copy the storage of a registered cloneable component type.
+pub(crate) struct ComponentClone {
+    snapshot: SnapshotFn,
+    restore: RestoreFn,
+    clone_component: CloneComponentFn,
+}
+
+impl ComponentClone {
+    pub fn of<C: Component + Clone>() -> Self {
+        Self {
+            snapshot: snapshot_components::<C>,
+            restore: restore_components::<C>,
+            clone_component: clone_component::<C>,
+        }
+    }
+
+    /// Copy one entity's component to another entity, if it has one.
+    #[inline]
+    pub fn clone_component(&self, all_storages: &mut AllStorages, src: EntityId, dst: EntityId) {
+        (self.clone_component)(all_storages, src, dst);
+    }
+}
+
+/// How to copy a registered cloneable unique type.
+pub(crate) struct UniqueClone {
+    snapshot: SnapshotFn,
+    restore: RestoreFn,
+}
+
+impl UniqueClone {
+    pub fn of<T: Unique + Clone>() -> Self {
+        Self {
+            snapshot: snapshot_unique::<T>,
+            restore: restore_unique::<T>,
+        }
+    }
+}
+
+/// A copy of a world's entities, and of every component and unique whose type was
+/// registered with [`cloneable`](crate::registry::ComponentConfig::cloneable).
+///
+/// The entity allocator is copied verbatim, so restoring a snapshot puts every entity back
+/// at the same index and version.
+pub struct Snapshot {
+    pub(crate) entities: EntityStorage,
+    pub(crate) components: Vec<StorageSnapshot>,
+    uniques: Vec<StorageSnapshot>,
+}
+
+pub(crate) struct StorageSnapshot {
+    pub type_id: TypeId,
+
+    /// The copied storage, or `None` if it didn't exist.
+    pub data: Option<Box<dyn Any + Send + Sync>>,
+    restore: RestoreFn,
+
+    /// How to diff the storage, if its component type is diffable.
+    pub diff: Option<ComponentDiff>,
+}
+
+impl<Data: WorldData> World<Data> {
+    /// Take a [`Snapshot`] of the world.
+    ///
+    /// Fails if a storage that needs to be copied is borrowed mutably.
+    pub fn snapshot(&self) -> QueryResult<Snapshot> {
+        let components = self
+            .registry
+            .components
+            .iter()
+            .filter_map(|registration| Some((registration, registration.clone.as_ref()?)))
+            .map(|(registration, clone)| {
+                Ok(StorageSnapshot {
+                    type_id: registration.type_id,
+                    data: (clone.snapshot)(&self.all_storages)?,
+                    restore: clone.restore,
+                    diff: registration.diff,
+                })
+            })
+            .collect::<QueryResult<_>>()?;
+
+        let uniques = self
+            .registry
+            .uniques
+            .iter()
+            .filter_map(|registration| Some((registration, registration.clone.as_ref()?)))
+            .map(|(registration, clone)| {
+                Ok(StorageSnapshot {
+                    type_id: registration.type_id,
+                    data: (clone.snapshot)(&self.all_storages)?,
+                    restore: clone.restore,
+                    diff: None,
+                })
+            })
+            .collect::<QueryResult<_>>()?;
+
+        Ok(Snapshot {
+            entities: self.all_storages.entities.clone(),
+            components,
+            uniques,
+        })
+    }
+
+    /// Put the world back into the state it was in when a [`Snapshot`] was taken.
+    ///
+    /// Entities that aren't alive in the snapshot are despawned first, removing all of
+    /// their components. The entity allocator is then restored verbatim. Copied components
+    /// that the snapshot doesn't have are removed, and every component in the snapshot is
+    /// inserted again, so it's marked as added or changed at the current tick and removals
+    /// show up in [`RemovedComponents`](crate::query::removed::RemovedComponents). Components and
+    /// uniques of other types are left as they are. The change tick isn't restored.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let despawned: Vec<EntityId> = self
+            .all_storages
+            .entities
+            .iter()
+            .filter(|&entity| !snapshot.entities.is_alive(entity))
+            .collect();
+        for entity in despawned {
+            for storage in self.all_storages.components.iter_mut() {
+                storage.remove_entity(entity, &self.all_storages.removed);
+            }
+        }
+
+        self.all_storages.entities.clone_from(&snapshot.entities);
+
+        for storage in snapshot.components.iter().chain(&snapshot.uniques) {
+            (storage.restore)(&mut self.all_storages, storage.data.as_deref());
+        }
+    }
+}
+
+fn snapshot_components<C: Component + Clone>(
+    all_storages: &AllStorages,
+) -> QueryResult<Option<Box<dyn Any + Send + Sync>>> {
+    match all_storages.components.borrow_ref::<ComponentStorage<C>>() {
+        Ok(storage) => Ok(Some(Box::new(storage.set.clone()))),
+        Err(QueryError::StorageMissing) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn restore_components<C: Component + Clone>(
+    all_storages: &mut AllStorages,
+    data: Option<&(dyn Any + Send + Sync)>,
+) {
+    let set = data.and_then(|data| data.downcast_ref::<SparseSet<TrackedComponent<C>>>());
+    let tick = all_storages.change_tick.get();
+    let log = all_storages.removed.get::<C>();
+
+    if set.is_some() && !all_storages.components.contains::<ComponentStorage<C>>() {
+        let _ = all_storages
+            .components
+            .insert(ComponentStorage::<C>::default());
+    }
+    let Some(storage) = all_storages.components.get_mut::<ComponentStorage<C>>() else {
+        return;
+    };
+
+    // Entities that weren't alive in the snapshot have already been despawned, so the
+    // components that are left belong to entities that survived the restore.
+    let removed: Vec<usize> = storage
+        .set
+        .iter_with_indices()
+        .map(|(index, _)| index)
+        .filter(|&index| !set.is_some_and(|set| set.contains(index)))
+        .collect();
+    for index in removed {
+        match all_storages.entities.alive_at(index) {
+            Some(entity) => {
+                let _ = storage.remove(entity, log);
+            }
+            None => {
+                let _ = storage.set.remove(index);
+            }
+        }
+    }
+
+    for (index, tracked) in set.into_iter().flat_map(SparseSet::iter_with_indices) {
+        storage.insert(index, tracked.component.clone(), tick);
+    }
+}
+
+fn clone_component<C: Component + Clone>(
+    all_storages: &mut AllStorages,
+    src: EntityId,
+    dst: EntityId,
+) {
+    let tick = all_storages.change_tick.get();
+    let Some(storage) = all_storages.components.get_mut::<ComponentStorage<C>>() else {
+        return;
+    };
+
+    if let Some(component) = storage.get(src.index()).cloned() {
+        storage.insert(dst.index(), component, tick);
+    }
+}
+
+fn snapshot_unique<T: Unique + Clone>(
+    all_storages: &AllStorages,
+) -> QueryResult<Option<Box<dyn Any + Send + Sync>>> {
+    match all_storages.uniques.borrow_ref::<UniqueStorage<T>>() {
+        Ok(storage) => Ok(Some(Box::new(storage.0.clone()))),
+        Err(QueryError::StorageMissing) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn restore_unique<T: Unique + Clone>(
+    all_storages: &mut AllStorages,
+    data: Option<&(dyn Any + Send + Sync)>,
+) {
+    match data.and_then(|data| data.downcast_ref::<T>()) {
+        Some(unique) => match all_storages.uniques.get_mut::<UniqueStorage<T>>() {
+            Some(storage) => storage.0.clone_from(unique),
+            None => {
+                let _ = all_storages.uniques.insert(UniqueStorage(unique.clone()));
+            }
+        },
+        None => {
+            let _ = all_storages.uniques.remove::<UniqueStorage<T>>();
+        }
+    }
+}
+
+/// A ring buffer of the most recent snapshots, keyed by frame number.
+///
+/// This is meant for rollback: push a snapshot every frame, then when a late input
+/// arrives, restore the snapshot for the frame it belongs to and simulate forward again.
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<(u64, Snapshot)>,
+    capacity: usize,
+}
+
+impl SnapshotBuffer {
+    /// Create a buffer that keeps the last `capacity` snapshots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "snapshot buffer capacity must be non-zero");
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add the snapshot for a frame, dropping the oldest one if the buffer is full.
+    ///
+    /// Snapshots for this frame or later ones are dropped first, since they were taken
+    /// before the simulation was rolled back.
+    pub fn push(&mut self, frame: u64, snapshot: Snapshot) {
+        while self
+            .snapshots
+            .back()
+            .is_some_and(|&(latest, _)| latest >= frame)
+        {
+            self.snapshots.pop_back();
+        }
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back((frame, snapshot));
+    }
+
+    /// Get the snapshot for a frame, if it's still in the buffer.
+    pub fn get(&self, frame: u64) -> Option<&Snapshot> {
+        let index = self
+            .snapshots
+            .binary_search_by_key(&frame, |&(frame, _)| frame)
+            .ok()?;
+        Some(&self.snapshots[index].1)
+    }
+
+    /// Get the most recent snapshot and its frame.
+    #[inline]
+    pub fn latest(&self) -> Option<(u64, &Snapshot)> {
+        self.snapshots
+            .back()
+            .map(|(frame, snapshot)| (*frame, snapshot))
+    }
+
+    /// Iterate over the frames that have snapshots, from oldest to newest.
+    #[inline]
+    pub fn frames(&self) -> impl Iterator<Item = u64> + '_ {
+        self.snapshots.iter().map(|&(frame, _)| frame)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
@@ -2,11 +2,25 @@ const PAGE_SIZE: usize = 4096;
 
 type Page<T> = [Option<T>];
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(super) struct SparseArray<T> {
     pages: Vec<Option<Box<Page<T>>>>,
 }
 
+impl<T: Clone> Clone for SparseArray<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            pages: self.pages.clone(),
+        }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        self.pages.clone_from(&source.pages);
+    }
+}
+
 impl<T> Default for SparseArray<T> {
     fn default() -> Self {
         Self { pages: vec![] }
@@ -25,13 +39,15 @@ impl<T> SparseArray<T> {
     #[inline]
     pub fn insert(&mut self, index: usize, element: T) -> Option<T> {
         let (page_index, offset) = page_index(index);
+        self.page_mut(page_index)[offset].replace(element)
+    }
 
-        if page_index >= self.pages.len() {
-            self.create_page(page_index);
-        }
-
-        let page = self.pages[page_index].as_deref_mut().unwrap();
-        page[offset].replace(element)
+    /// Make sure the page containing an index exists, so that inserting at that index
+    /// won't allocate.
+    #[inline]
+    pub fn reserve_index(&mut self, index: usize) {
+        let (page_index, _) = page_index(index);
+        self.page_mut(page_index);
     }
 
     #[inline]
@@ -41,23 +57,18 @@ impl<T> SparseArray<T> {
         page[offset].take()
     }
 
-    /// Panics if the page already exists.
+    /// Get a page, creating it if it doesn't exist.
     #[inline]
-    fn create_page(&mut self, page_index: usize) {
-        debug_assert!(page_index >= self.pages.len());
-
-        let new_len = page_index + 1;
-        let none_pages = new_len - self.pages.len() - 1;
-
-        self.pages.reserve(none_pages);
-        self.pages
-            .extend(std::iter::repeat_with(|| None).take(none_pages));
-
-        let page = std::iter::repeat_with(|| None)
-            .take(PAGE_SIZE)
-            .collect::<Box<_>>();
+    fn page_mut(&mut self, page_index: usize) -> &mut Page<T> {
+        if page_index >= self.pages.len() {
+            self.pages.resize_with(page_index + 1, || None);
+        }
 
-        self.pages.push(Some(page));
+        self.pages[page_index].get_or_insert_with(|| {
+            std::iter::repeat_with(|| None)
+                .take(PAGE_SIZE)
+                .collect::<Box<_>>()
+        })
     }
 }
 
@@ -87,6 +98,23 @@ mod tests {
         assert_eq!(arr.get(PAGE_SIZE * 3), Some(&20));
 
         assert_eq!(arr.pages.len(), 4);
+
+        // skipped page
+        arr.insert(PAGE_SIZE, 24);
+        assert_eq!(arr.get(PAGE_SIZE), Some(&24));
+
+        assert_eq!(arr.pages.len(), 4);
+    }
+
+    #[test]
+    fn reserve_index() {
+        let mut arr = SparseArray::<usize>::default();
+
+        arr.reserve_index(PAGE_SIZE * 2);
+        assert_eq!(arr.pages.len(), 3);
+        assert!(arr.pages[1].is_none());
+        assert!(arr.pages[2].is_some());
+        assert_eq!(arr.get(PAGE_SIZE * 2), None);
     }
 
     #[test]
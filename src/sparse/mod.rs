@@ -1,4 +1,4 @@
-pub(crate) use self::set::SparseSet;
+pub(crate) use self::set::{SparseSet, SparseSetRawMut};
 
 mod array;
 mod set;
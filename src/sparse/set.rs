@@ -1,6 +1,11 @@
+use std::marker::PhantomData;
+use std::ptr;
+
+use rayon::prelude::*;
+
 use super::array::SparseArray;
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug)]
 struct DenseEntry<T> {
     sparse_index: usize,
     element: T,
@@ -11,12 +16,45 @@ struct DenseEntry<T> {
 ///
 /// It's implemented as a sparse array of indices mapping to a dense array of the actual elements.
 /// The sparse array is paginated so that the memory usage is acceptable.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(crate) struct SparseSet<T> {
     sparse: SparseArray<usize>,
     dense: Vec<DenseEntry<T>>,
 }
 
+// Implemented by hand so that `clone_from` reuses the existing allocations.
+impl<T: Clone> Clone for DenseEntry<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            sparse_index: self.sparse_index,
+            element: self.element.clone(),
+        }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        self.sparse_index = source.sparse_index;
+        self.element.clone_from(&source.element);
+    }
+}
+
+impl<T: Clone> Clone for SparseSet<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            sparse: self.sparse.clone(),
+            dense: self.dense.clone(),
+        }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        self.sparse.clone_from(&source.sparse);
+        self.dense.clone_from(&source.dense);
+    }
+}
+
 impl<T> Default for SparseSet<T> {
     fn default() -> Self {
         Self {
@@ -27,6 +65,7 @@ impl<T> Default for SparseSet<T> {
 }
 
 impl<T> SparseSet<T> {
+    #[allow(dead_code)]
     #[inline]
     pub fn new() -> Self {
         Self::default()
@@ -84,7 +123,7 @@ impl<T> SparseSet<T> {
         // sparse array entry.
         if dense_index < self.dense.len() {
             let sparse_swapped_index = self.dense[dense_index].sparse_index;
-            self.sparse.insert(sparse_swapped_index, index);
+            self.sparse.insert(sparse_swapped_index, dense_index);
         }
 
         Some(removed.element)
@@ -118,10 +157,112 @@ impl<T> SparseSet<T> {
             .map(|dense_entry| (dense_entry.sparse_index, &mut dense_entry.element))
     }
 
+    /// Iterate mutably over the elements in parallel, splitting the dense array into
+    /// chunks.
+    #[inline]
+    pub fn par_iter_mut_with_indices(
+        &mut self,
+    ) -> impl IndexedParallelIterator<Item = (usize, &mut T)>
+    where
+        T: Send,
+    {
+        self.dense
+            .par_iter_mut()
+            .map(|dense_entry| (dense_entry.sparse_index, &mut dense_entry.element))
+    }
+
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        self.sparse.get(index).is_some()
+    }
+
+    /// The number of elements in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    /// Get the sparse index of the element at a position in the dense array.
+    #[inline]
+    pub fn sparse_index_at(&self, dense_index: usize) -> usize {
+        self.dense[dense_index].sparse_index
+    }
+
+    /// Reserve space for elements at some indices, so that inserting them won't
+    /// allocate.
+    #[inline]
+    pub fn reserve(&mut self, indices: impl ExactSizeIterator<Item = usize>) {
+        self.dense.reserve(indices.len());
+        for index in indices {
+            self.sparse.reserve_index(index);
+        }
+    }
+
+    /// Get a view of this set that can hand out mutable references to several
+    /// elements at once.
+    #[inline]
+    pub fn raw_mut(&mut self) -> SparseSetRawMut<'_, T> {
+        SparseSetRawMut {
+            sparse: &self.sparse,
+            dense: self.dense.as_mut_ptr(),
+            len: self.dense.len(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A mutable view of a sparse set that can hand out mutable references to
+/// distinct elements at the same time.
+///
+/// The sparse array can't be modified through this view, so the positions of the
+/// elements in the dense array are fixed for its lifetime.
+pub(crate) struct SparseSetRawMut<'a, T> {
+    sparse: &'a SparseArray<usize>,
+    dense: *mut DenseEntry<T>,
+    len: usize,
+    _marker: PhantomData<&'a mut [DenseEntry<T>]>,
+}
+
+// The view hands out mutable references to distinct elements, like a mutable slice
+// split between threads.
+unsafe impl<T: Send> Send for SparseSetRawMut<'_, T> {}
+unsafe impl<T: Send + Sync> Sync for SparseSetRawMut<'_, T> {}
+
+impl<'a, T> SparseSetRawMut<'a, T> {
     #[inline]
     pub fn contains(&self, index: usize) -> bool {
         self.sparse.get(index).is_some()
     }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Get the sparse index of the element at a position in the dense array.
+    #[inline]
+    pub fn sparse_index_at(&self, dense_index: usize) -> usize {
+        assert!(dense_index < self.len);
+
+        // Only the sparse index is read, so this doesn't alias any element that has
+        // been handed out.
+        unsafe { ptr::addr_of!((*self.dense.add(dense_index)).sparse_index).read() }
+    }
+
+    /// Get a mutable reference to an element.
+    ///
+    /// # Safety
+    ///
+    /// No other reference to the element at this index may be alive for `'a`.
+    #[inline]
+    pub unsafe fn get_mut(&self, index: usize) -> Option<&'a mut T> {
+        let dense_index = *self.sparse.get(index)?;
+        debug_assert!(dense_index < self.len);
+
+        Some(&mut *ptr::addr_of_mut!(
+            (*self.dense.add(dense_index)).element
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -153,4 +294,54 @@ mod tests {
         assert_eq!(set.remove(1), Some(16));
         assert_eq!(set.remove(1), None);
     }
+
+    #[test]
+    fn par_iter_mut_with_indices() {
+        let mut set = SparseSet::default();
+        for i in 0..1000 {
+            set.insert(i * 2, i);
+        }
+
+        set.par_iter_mut_with_indices()
+            .for_each(|(index, element)| *element += index);
+
+        for i in 0..1000 {
+            assert_eq!(set.get(i * 2), Some(&(i * 3)));
+        }
+    }
+
+    #[test]
+    fn remove_swaps_last_element() {
+        let mut set = SparseSet::default();
+
+        set.insert(5, 12);
+        set.insert(7, 16);
+        set.insert(9, 20);
+
+        assert_eq!(set.remove(5), Some(12));
+
+        assert_eq!(set.get(7), Some(&16));
+        assert_eq!(set.get(9), Some(&20));
+
+        assert_eq!(set.remove(9), Some(20));
+        assert_eq!(set.get(7), Some(&16));
+    }
+
+    #[test]
+    fn raw_mut_disjoint() {
+        let mut set = SparseSet::default();
+
+        set.insert(3, 12);
+        set.insert(8, 16);
+
+        let raw = set.raw_mut();
+        assert_eq!(raw.sparse_index_at(1), 8);
+
+        let a = unsafe { raw.get_mut(3) }.unwrap();
+        let b = unsafe { raw.get_mut(8) }.unwrap();
+        std::mem::swap(a, b);
+
+        assert_eq!(set.get(3), Some(&16));
+        assert_eq!(set.get(8), Some(&12));
+    }
 }
@@ -0,0 +1,129 @@
+use crate::query::component::QueryCompMut;
+use crate::query::QueryResult;
+use crate::storage::component::Component;
+use crate::storage::entities::EntityId;
+
+#[doc(hidden)]
+pub use crate::erased_storages::AllStorages;
+
+/// A group of components that can be inserted into or removed from an entity together.
+///
+/// This is implemented for every component, for tuples of up to eight bundles, and can be
+/// derived for structs whose fields are all bundles.
+pub trait Bundle: Send + Sync + Sized + 'static {
+    /// The storages of every component in the bundle, borrowed mutably.
+    #[doc(hidden)]
+    type Storages<'a>;
+
+    #[doc(hidden)]
+    fn borrow_storages(all_storages: &AllStorages) -> QueryResult<Self::Storages<'_>>;
+
+    /// Reserve space for the components of some entities.
+    #[doc(hidden)]
+    fn reserve(storages: &mut Self::Storages<'_>, entities: &[EntityId]);
+
+    /// Insert the components into an entity, which must be alive.
+    #[doc(hidden)]
+    fn insert(self, storages: &mut Self::Storages<'_>, entity: EntityId) -> QueryResult<()>;
+
+    /// Remove every component in the bundle from an entity, returning the bundle if the
+    /// entity had all of them.
+    #[doc(hidden)]
+    fn remove(storages: &mut Self::Storages<'_>, entity: EntityId) -> QueryResult<Option<Self>>;
+}
+
+impl<C: Component> Bundle for C {
+    type Storages<'a> = QueryCompMut<'a, C>;
+
+    #[inline]
+    fn borrow_storages(all_storages: &AllStorages) -> QueryResult<Self::Storages<'_>> {
+        QueryCompMut::borrow_from(all_storages)
+    }
+
+    #[inline]
+    fn reserve(storages: &mut Self::Storages<'_>, entities: &[EntityId]) {
+        storages.reserve(entities);
+    }
+
+    #[inline]
+    fn insert(self, storages: &mut Self::Storages<'_>, entity: EntityId) -> QueryResult<()> {
+        storages.insert(entity, self)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn remove(storages: &mut Self::Storages<'_>, entity: EntityId) -> QueryResult<Option<Self>> {
+        storages.remove(entity)
+    }
+}
+
+macro_rules! impl_bundle {
+    ($(($bundle:ident, $index:tt)),*) => {
+        impl<$($bundle: Bundle),*> Bundle for ($($bundle,)*) {
+            type Storages<'a> = ($($bundle::Storages<'a>,)*);
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn borrow_storages(all_storages: &AllStorages) -> QueryResult<Self::Storages<'_>> {
+                Ok(($($bundle::borrow_storages(all_storages)?,)*))
+            }
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn reserve(storages: &mut Self::Storages<'_>, entities: &[EntityId]) {
+                $($bundle::reserve(&mut storages.$index, entities);)*
+            }
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn insert(
+                self,
+                storages: &mut Self::Storages<'_>,
+                entity: EntityId,
+            ) -> QueryResult<()> {
+                $(self.$index.insert(&mut storages.$index, entity)?;)*
+                Ok(())
+            }
+
+            #[allow(unused_variables, non_snake_case, unreachable_patterns)]
+            #[inline]
+            fn remove(
+                storages: &mut Self::Storages<'_>,
+                entity: EntityId,
+            ) -> QueryResult<Option<Self>> {
+                $(let $bundle = $bundle::remove(&mut storages.$index, entity)?;)*
+                Ok(match ($($bundle,)*) {
+                    ($(Some($bundle),)*) => Some(($($bundle,)*)),
+                    _ => None,
+                })
+            }
+        }
+    };
+}
+
+impl_bundle!();
+impl_bundle!((B0, 0));
+impl_bundle!((B0, 0), (B1, 1));
+impl_bundle!((B0, 0), (B1, 1), (B2, 2));
+impl_bundle!((B0, 0), (B1, 1), (B2, 2), (B3, 3));
+impl_bundle!((B0, 0), (B1, 1), (B2, 2), (B3, 3), (B4, 4));
+impl_bundle!((B0, 0), (B1, 1), (B2, 2), (B3, 3), (B4, 4), (B5, 5));
+impl_bundle!(
+    (B0, 0),
+    (B1, 1),
+    (B2, 2),
+    (B3, 3),
+    (B4, 4),
+    (B5, 5),
+    (B6, 6)
+);
+impl_bundle!(
+    (B0, 0),
+    (B1, 1),
+    (B2, 2),
+    (B3, 3),
+    (B4, 4),
+    (B5, 5),
+    (B6, 6),
+    (B7, 7)
+);
@@ -1,12 +1,271 @@
-use crate::sparse::SparseSet;
+use std::any::{type_name, TypeId};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
-pub trait Component: 'static {}
+use elsa::sync::FrozenMap;
+use rayon::prelude::*;
 
-pub(crate) struct ComponentStorage<C: Component>(pub SparseSet<C>);
+use crate::query::component::Mut;
+use crate::sparse::{SparseSet, SparseSetRawMut};
+
+use super::entities::EntityId;
+use super::tick::{ComponentTicks, Tick};
+
+pub trait Component: Send + Sync + 'static {}
+
+/// Information about a component type, for debugging and tooling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentInfo {
+    pub name: &'static str,
+    pub type_id: TypeId,
+    pub size: usize,
+}
+
+impl ComponentInfo {
+    #[inline]
+    pub fn of<C: Component>() -> Self {
+        Self {
+            name: type_name::<C>(),
+            type_id: TypeId::of::<C>(),
+            size: std::mem::size_of::<C>(),
+        }
+    }
+}
+
+pub(crate) struct TrackedComponent<C> {
+    pub component: C,
+    pub ticks: ComponentTicks,
+}
+
+impl<C: Clone> Clone for TrackedComponent<C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            component: self.component.clone(),
+            ticks: self.ticks,
+        }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        self.component.clone_from(&source.component);
+        self.ticks = source.ticks;
+    }
+}
+
+pub(crate) struct ComponentStorage<C: Component> {
+    pub set: SparseSet<TrackedComponent<C>>,
+}
 
 impl<C: Component> Default for ComponentStorage<C> {
     #[inline]
     fn default() -> Self {
-        ComponentStorage(SparseSet::default())
+        ComponentStorage {
+            set: SparseSet::default(),
+        }
+    }
+}
+
+impl<C: Component> ComponentStorage<C> {
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&C> {
+        self.set.get(index).map(|tracked| &tracked.component)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: usize, tick: Tick) -> Option<Mut<'_, C>> {
+        self.set
+            .get_mut(index)
+            .map(|tracked| Mut::new(&mut tracked.component, &mut tracked.ticks, tick))
+    }
+
+    /// Insert a component, marking it as added if it is new and as changed otherwise.
+    #[inline]
+    pub fn insert(&mut self, index: usize, component: C, tick: Tick) -> Option<C> {
+        match self.set.get_mut(index) {
+            Some(tracked) => {
+                tracked.ticks.changed = tick;
+                Some(std::mem::replace(&mut tracked.component, component))
+            }
+            None => {
+                let ticks = ComponentTicks::new(tick);
+                self.set
+                    .insert(index, TrackedComponent { component, ticks });
+                None
+            }
+        }
+    }
+
+    /// Remove an entity's component, recording the removal in `log` if there was one.
+    #[inline]
+    pub fn remove(&mut self, entity: EntityId, log: &Mutex<RemovalLog>) -> Option<C> {
+        let tracked = self.set.remove(entity.index())?;
+        RemovalLog::lock(log).push(entity);
+        Some(tracked.component)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &C> {
+        self.set.iter().map(|tracked| &tracked.component)
+    }
+
+    #[inline]
+    pub fn iter_with_indices(&self) -> impl Iterator<Item = (usize, &C)> {
+        self.set
+            .iter_with_indices()
+            .map(|(index, tracked)| (index, &tracked.component))
+    }
+
+    #[inline]
+    pub fn iter_ticks_with_indices(&self) -> impl Iterator<Item = (usize, &C, ComponentTicks)> {
+        self.set
+            .iter_with_indices()
+            .map(|(index, tracked)| (index, &tracked.component, tracked.ticks))
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self, tick: Tick) -> impl Iterator<Item = Mut<'_, C>> {
+        self.set
+            .iter_mut()
+            .map(move |tracked| Mut::new(&mut tracked.component, &mut tracked.ticks, tick))
+    }
+
+    #[inline]
+    pub fn iter_mut_with_indices(
+        &mut self,
+        tick: Tick,
+    ) -> impl Iterator<Item = (usize, Mut<'_, C>)> {
+        self.set
+            .iter_mut_with_indices()
+            .map(move |(index, tracked)| {
+                let component = Mut::new(&mut tracked.component, &mut tracked.ticks, tick);
+                (index, component)
+            })
+    }
+
+    #[inline]
+    pub fn par_iter_mut_with_indices(
+        &mut self,
+        tick: Tick,
+    ) -> impl IndexedParallelIterator<Item = (usize, Mut<'_, C>)> {
+        self.set
+            .par_iter_mut_with_indices()
+            .map(move |(index, tracked)| {
+                let component = Mut::new(&mut tracked.component, &mut tracked.ticks, tick);
+                (index, component)
+            })
+    }
+
+    /// Reserve space for components for some entities.
+    #[inline]
+    pub fn reserve(&mut self, entities: &[EntityId]) {
+        self.set
+            .reserve(entities.iter().map(|entity| entity.index()));
+    }
+
+    #[inline]
+    pub fn raw_mut(&mut self) -> SparseSetRawMut<'_, TrackedComponent<C>> {
+        self.set.raw_mut()
+    }
+}
+
+/// A log of the entities that have had a component removed.
+///
+/// Readers keep a cursor into the log, which is a sequence number rather than a
+/// position so that the log can be cleared without invalidating cursors.
+#[derive(Default)]
+pub(crate) struct RemovalLog {
+    entities: Vec<EntityId>,
+    start: usize,
+}
+
+impl RemovalLog {
+    /// Lock a shared log.
+    ///
+    /// A panic while the log was locked can't leave it in an inconsistent state, so
+    /// poisoning is ignored.
+    #[inline]
+    pub fn lock(log: &Mutex<Self>) -> MutexGuard<'_, Self> {
+        log.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    #[inline]
+    pub fn push(&mut self, entity: EntityId) {
+        self.entities.push(entity);
+    }
+
+    /// Get the removals after a cursor, and advance the cursor past them.
+    #[inline]
+    pub fn read(&self, cursor: &mut usize) -> &[EntityId] {
+        let from = cursor.saturating_sub(self.start).min(self.entities.len());
+        *cursor = self.start + self.entities.len();
+        &self.entities[from..]
+    }
+
+    #[inline]
+    pub fn entities(&self) -> &[EntityId] {
+        &self.entities
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.start += self.entities.len();
+        self.entities.clear();
+    }
+}
+
+/// The removal log of each component type.
+///
+/// These are kept apart from the component storages, so that removals can be read while
+/// the components are borrowed mutably. Each log is behind a mutex rather than a borrow
+/// flag, so that components can be removed while the log is being read.
+#[derive(Default)]
+pub(crate) struct RemovalLogs {
+    logs: FrozenMap<TypeId, Box<Mutex<RemovalLog>>>,
+}
+
+impl RemovalLogs {
+    /// Get the log for a component type, creating it if it doesn't exist.
+    #[inline]
+    pub fn get<C: Component>(&self) -> &Mutex<RemovalLog> {
+        let type_id = TypeId::of::<C>();
+        // Another thread may insert the log first, in which case that one is kept.
+        self.logs
+            .get(&type_id)
+            .unwrap_or_else(|| self.logs.insert_with(type_id, Default::default))
+    }
+
+    /// Forget every recorded removal.
+    pub fn clear(&mut self) {
+        for log in self.logs.as_mut().values_mut() {
+            log.get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::entities::EntityStorage;
+
+    #[test]
+    fn removal_log_cursors() {
+        let mut entities = EntityStorage::new();
+        let a = entities.alloc().unwrap();
+        let b = entities.alloc().unwrap();
+
+        let mut log = RemovalLog::default();
+        let mut cursor = 0;
+
+        log.push(a);
+        assert_eq!(log.read(&mut cursor), &[a]);
+        assert_eq!(log.read(&mut cursor), &[]);
+
+        log.clear();
+        log.push(b);
+        assert_eq!(log.read(&mut cursor), &[b]);
+
+        let mut late_cursor = 0;
+        assert_eq!(log.read(&mut late_cursor), &[b]);
     }
 }
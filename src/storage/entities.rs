@@ -2,6 +2,7 @@
 
 use std::num::NonZeroU32;
 use std::slice;
+use std::sync::atomic::{AtomicIsize, Ordering};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct EntityId {
@@ -37,6 +38,12 @@ pub enum EntityError {
 
     #[error("entity is dead")]
     DeadEntity,
+
+    #[error("entity is already alive")]
+    AliveEntity,
+
+    #[error("entity version is older than its index's")]
+    StaleEntity,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -47,7 +54,7 @@ struct EntityEntry {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum EntryState {
-    Dead(NonZeroU32),
+    Dead,
     Alive,
 }
 
@@ -60,20 +67,19 @@ impl EntityEntry {
     }
 }
 
-impl EntryState {
-    fn next_free(self) -> Option<NonZeroU32> {
-        match self {
-            Self::Dead(index) => Some(index),
-            Self::Alive => None,
-        }
-    }
-}
-
+/// Allocates entity ids and keeps track of which entities are alive.
 #[derive(Debug)]
-pub(crate) struct EntityStorage {
+pub struct EntityStorage {
     entries: Vec<EntityEntry>,
-    next_free: NonZeroU32,
-    num_free: usize,
+
+    /// Indices of dead entities that can be reused.
+    free: Vec<NonZeroU32>,
+
+    /// The number of entries in `free` that haven't been reserved.
+    ///
+    /// If this is negative, every free index has been reserved, and this is minus the
+    /// number of new indices reserved past the end of `entries`.
+    free_cursor: AtomicIsize,
 }
 
 impl Default for EntityStorage {
@@ -83,32 +89,47 @@ impl Default for EntityStorage {
     }
 }
 
+impl Clone for EntityStorage {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            free: self.free.clone(),
+            free_cursor: AtomicIsize::new(self.free_cursor.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.entries.clone_from(&source.entries);
+        self.free.clone_from(&source.free);
+        *self.free_cursor.get_mut() = source.free_cursor.load(Ordering::Relaxed);
+    }
+}
+
 impl EntityStorage {
-    pub fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             entries: vec![EntityEntry {
                 // We need one dummy entity so that no real entity has an index of zero.
 
                 // This isn't free, but it can't be marked as occupied, and it
-                // won't be accessible by the free list, so it's fine.
-                state: EntryState::Dead(NonZeroU32::new(1).unwrap()),
+                // won't be in the free list, so it's fine.
+                state: EntryState::Dead,
                 version: 0,
             }],
-            // it doesn't matter what this is
-            next_free: NonZeroU32::new(1).unwrap(),
-            num_free: 0,
+            free: vec![],
+            free_cursor: AtomicIsize::new(0),
         }
     }
 
     /// Allocate a new entity.
-    pub fn alloc(&mut self) -> Result<EntityId, EntityError> {
-        if self.num_free > 0 {
-            let entry = &mut self.entries[u32::from(self.next_free) as usize];
-            let index = self.next_free;
-
-            // Pop from the implicit linked list.
-            self.next_free = entry.state.next_free().unwrap();
-            self.num_free -= 1;
+    pub(crate) fn alloc(&mut self) -> Result<EntityId, EntityError> {
+        // Reserved indices must be taken before any others are handed out.
+        self.flush_reserved();
+
+        if let Some(index) = self.free.pop() {
+            *self.free_cursor.get_mut() -= 1;
+
+            let entry = &mut self.entries[u32::from(index) as usize];
             entry.state = EntryState::Alive;
 
             Ok(entry.as_id(index))
@@ -131,8 +152,182 @@ impl EntityStorage {
         }
     }
 
+    /// Allocate many entities at once.
+    ///
+    /// Either every entity is allocated, or none are.
+    pub(crate) fn alloc_batch(&mut self, count: usize) -> Result<Vec<EntityId>, EntityError> {
+        self.flush_reserved();
+
+        let from_free = count.min(self.free.len());
+        let new = count - from_free;
+
+        if self.entries.len() + new > u32::MAX as usize {
+            return Err(EntityError::OutOfEntities);
+        }
+
+        let mut entities = Vec::with_capacity(count);
+
+        for index in self.free.drain(self.free.len() - from_free..).rev() {
+            let entry = &mut self.entries[u32::from(index) as usize];
+            entry.state = EntryState::Alive;
+            entities.push(entry.as_id(index));
+        }
+        *self.free_cursor.get_mut() = self.free.len() as isize;
+
+        let entry = EntityEntry {
+            state: EntryState::Alive,
+            version: 0,
+        };
+        let start = self.entries.len();
+        self.entries.resize(start + new, entry);
+        entities.extend(
+            (start..start + new).map(|index| entry.as_id(NonZeroU32::new(index as u32).unwrap())),
+        );
+
+        Ok(entities)
+    }
+
+    /// Reserve an entity through a shared reference.
+    ///
+    /// Free indices are reused first, then new indices are handed out past the end of
+    /// the storage. The entity isn't alive until [`flush_reserved`](Self::flush_reserved)
+    /// is called.
+    pub(crate) fn reserve(&self) -> Result<EntityId, EntityError> {
+        let cursor = self.free_cursor.fetch_sub(1, Ordering::Relaxed);
+
+        if cursor > 0 {
+            let index = self.free[cursor as usize - 1];
+            let entry = &self.entries[u32::from(index) as usize];
+            return Ok(entry.as_id(index));
+        }
+
+        // Every free index has been reserved, so hand out a new one.
+        let index = self.entries.len() + cursor.unsigned_abs();
+
+        if index >= u32::MAX as usize {
+            return Err(EntityError::OutOfEntities);
+        }
+
+        Ok(EntityId {
+            index: NonZeroU32::new(index as u32).unwrap(),
+            version: 0,
+        })
+    }
+
+    /// Make all reserved entities alive.
+    pub(crate) fn flush_reserved(&mut self) {
+        let cursor = *self.free_cursor.get_mut();
+
+        // Reserved free indices are at the end of the free list.
+        let reserved_free = self.free.len() - cursor.clamp(0, self.free.len() as isize) as usize;
+        for index in self.free.drain(self.free.len() - reserved_free..) {
+            self.entries[u32::from(index) as usize].state = EntryState::Alive;
+        }
+
+        if cursor < 0 {
+            // Reservations that failed because we ran out of entities are ignored.
+            let max_new = u32::MAX as usize - self.entries.len();
+            let new = cursor.unsigned_abs().min(max_new);
+
+            let entity = EntityEntry {
+                state: EntryState::Alive,
+                version: 0,
+            };
+            self.entries.resize(self.entries.len() + new, entity);
+        }
+
+        *self.free_cursor.get_mut() = self.free.len() as isize;
+    }
+
+    /// Check that [`alloc_exact`](Self::alloc_exact) could make entities alive once the
+    /// `despawning` entities have been deallocated.
+    ///
+    /// Each entity's index must be dead (or past the end of the storage), and its version
+    /// must be at least the version the index was left at, so that stale ids aren't
+    /// brought back to life.
+    pub(crate) fn check_alloc_exact(
+        &self,
+        entities: &[EntityId],
+        despawning: &[EntityId],
+    ) -> Result<(), EntityError> {
+        let mut despawning = despawning.to_vec();
+        despawning.sort_unstable_by_key(EntityId::index);
+
+        for entity in entities {
+            if entity.index() >= u32::MAX as usize {
+                return Err(EntityError::OutOfEntities);
+            }
+
+            // The lowest version the index can be given, or `None` if it's in use.
+            let min_version =
+                match despawning.binary_search_by_key(&entity.index(), EntityId::index) {
+                    Ok(i) => Some(despawning[i].version.saturating_add(1)),
+                    Err(_) => match self.entries.get(entity.index()) {
+                        None => Some(0),
+                        Some(entry) => match entry.state {
+                            EntryState::Alive => None,
+                            EntryState::Dead => Some(entry.version),
+                        },
+                    },
+                };
+
+            let Some(min_version) = min_version else {
+                return Err(EntityError::AliveEntity);
+            };
+
+            // An alive entity's version must be incrementable when it's despawned.
+            if entity.version < min_version || entity.version == u32::MAX {
+                return Err(EntityError::StaleEntity);
+            }
+        }
+
+        let mut indices: Vec<usize> = entities.iter().map(EntityId::index).collect();
+        indices.sort_unstable();
+        if indices.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err(EntityError::AliveEntity);
+        }
+
+        Ok(())
+    }
+
+    /// Make specific entities alive, as if they'd been allocated by another storage.
+    ///
+    /// Fails without changing anything if [`check_alloc_exact`](Self::check_alloc_exact)
+    /// does. Indices skipped past the end of the storage are added to the free list.
+    pub(crate) fn alloc_exact(&mut self, entities: &[EntityId]) -> Result<(), EntityError> {
+        self.flush_reserved();
+        self.check_alloc_exact(entities, &[])?;
+
+        let Some(bound) = entities.iter().map(|entity| entity.index() + 1).max() else {
+            return Ok(());
+        };
+
+        for index in self.entries.len()..bound {
+            self.entries.push(EntityEntry {
+                state: EntryState::Dead,
+                version: 0,
+            });
+            self.free.push(NonZeroU32::new(index as u32).unwrap());
+        }
+
+        for entity in entities {
+            let entry = &mut self.entries[entity.index()];
+            entry.state = EntryState::Alive;
+            entry.version = entity.version;
+        }
+
+        let entries = &self.entries;
+        self.free
+            .retain(|&index| entries[u32::from(index) as usize].state == EntryState::Dead);
+        *self.free_cursor.get_mut() = self.free.len() as isize;
+
+        Ok(())
+    }
+
     /// Deallocate an entity.
-    pub fn dealloc(&mut self, entity: EntityId) -> Result<(), EntityError> {
+    pub(crate) fn dealloc(&mut self, entity: EntityId) -> Result<(), EntityError> {
+        self.flush_reserved();
+
         if !self.is_alive(entity) {
             return Err(EntityError::DeadEntity);
         }
@@ -142,15 +337,15 @@ impl EntityStorage {
         // Increment the version.
         // Version will not be greater than `u32::MAX` - 1, so it won't overflow.
         entry.version += 1;
+        entry.state = EntryState::Dead;
 
-        // Recycle this index if possible by adding it to the implicit linked list.
+        // Recycle this index if possible by adding it to the free list.
         // The entity can't be reused if its new version is `u32::MAX`, because its
         // version wouldn't be incrementable when it was despawned.
 
         if entry.version < u32::MAX {
-            entry.state = EntryState::Dead(self.next_free);
-            self.next_free = entity.index;
-            self.num_free += 1;
+            self.free.push(entity.index);
+            *self.free_cursor.get_mut() += 1;
         }
 
         Ok(())
@@ -158,7 +353,7 @@ impl EntityStorage {
 
     /// Check if an entity is alive (and present in this storage).
     #[inline]
-    pub fn is_alive(&self, entity: EntityId) -> bool {
+    pub(crate) fn is_alive(&self, entity: EntityId) -> bool {
         let Some(stored) = self.entries.get(entity.index()) else {
             return false;
         };
@@ -173,9 +368,27 @@ impl EntityStorage {
         stored.state == EntryState::Alive && stored.version == entity.version
     }
 
+    /// Get an upper bound on the indices of alive entities.
+    #[inline]
+    pub(crate) fn index_bound(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Get the alive entity at an index, if there is one.
+    #[inline]
+    pub(crate) fn alive_at(&self, index: usize) -> Option<EntityId> {
+        let index = NonZeroU32::new(u32::try_from(index).ok()?)?;
+        let entry = self.entries.get(u32::from(index) as usize)?;
+
+        match entry.state {
+            EntryState::Alive => Some(entry.as_id(index)),
+            EntryState::Dead => None,
+        }
+    }
+
     /// Iterate over all alive entities.
     #[inline]
-    pub fn iter(&self) -> EntityIter {
+    pub(crate) fn iter(&self) -> EntityIter<'_> {
         EntityIter {
             iter: self.entries.iter(),
             index: 0,
@@ -183,6 +396,99 @@ impl EntityStorage {
     }
 }
 
+/// The state of an [`EntityStorage`], in a form that can be serialized.
+///
+/// Entries are stored as `(version, alive)` pairs, starting from index one.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct SavedEntities {
+    entries: Vec<(u32, bool)>,
+    free: Vec<u32>,
+}
+
+#[cfg(feature = "serde")]
+impl EntityStorage {
+    /// Save the allocator's state, treating reserved entities as alive.
+    pub(crate) fn save(&self) -> SavedEntities {
+        let cursor = self.free_cursor.load(Ordering::Relaxed);
+        let free_len = cursor.clamp(0, self.free.len() as isize) as usize;
+
+        let mut entries: Vec<_> = self.entries[1..]
+            .iter()
+            .map(|entry| (entry.version, matches!(entry.state, EntryState::Alive)))
+            .collect();
+
+        for &index in &self.free[free_len..] {
+            entries[u32::from(index) as usize - 1].1 = true;
+        }
+
+        if cursor < 0 {
+            let max_new = u32::MAX as usize - self.entries.len();
+            let new = cursor.unsigned_abs().min(max_new);
+            entries.resize(entries.len() + new, (0, true));
+        }
+
+        SavedEntities {
+            entries,
+            free: self.free[..free_len]
+                .iter()
+                .map(|&index| index.into())
+                .collect(),
+        }
+    }
+
+    /// Restore a saved state, checking that it's consistent.
+    pub(crate) fn load(saved: SavedEntities) -> Result<Self, &'static str> {
+        if saved.entries.len() >= u32::MAX as usize {
+            return Err("too many entities");
+        }
+
+        // An alive entity's version is incremented when it's despawned, and an index at
+        // the maximum version can never be reused.
+        if saved
+            .entries
+            .iter()
+            .any(|&(version, alive)| alive && version == u32::MAX)
+        {
+            return Err("alive entity has the maximum version");
+        }
+
+        let mut storage = Self::new();
+        storage
+            .entries
+            .extend(saved.entries.iter().map(|&(version, alive)| EntityEntry {
+                state: if alive {
+                    EntryState::Alive
+                } else {
+                    EntryState::Dead
+                },
+                version,
+            }));
+
+        let mut is_free = vec![false; storage.entries.len()];
+        for index in saved.free {
+            let index = NonZeroU32::new(index).ok_or("free entity index is zero")?;
+            let Some(entry) = storage.entries.get(u32::from(index) as usize) else {
+                return Err("free entity index is out of bounds");
+            };
+
+            let free = &mut is_free[u32::from(index) as usize];
+            if matches!(entry.state, EntryState::Alive) || *free {
+                return Err("free entity is alive or freed twice");
+            }
+            if entry.version == u32::MAX {
+                return Err("free entity has the maximum version");
+            }
+            *free = true;
+
+            storage.free.push(index);
+        }
+
+        *storage.free_cursor.get_mut() = storage.free.len() as isize;
+        Ok(storage)
+    }
+}
+
 pub struct EntityIter<'a> {
     iter: slice::Iter<'a, EntityEntry>,
     index: u32,
@@ -266,6 +572,201 @@ mod tests {
         assert_eq!(c, EntityId::new(3, 0).unwrap());
     }
 
+    #[test]
+    fn alloc_batch() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        let b = storage.alloc().unwrap();
+        storage.dealloc(a).unwrap();
+        storage.dealloc(b).unwrap();
+
+        let batch = storage.alloc_batch(3).unwrap();
+        assert_eq!(
+            batch,
+            vec![
+                EntityId::new(2, 1).unwrap(),
+                EntityId::new(1, 1).unwrap(),
+                EntityId::new(3, 0).unwrap(),
+            ]
+        );
+        assert!(batch.iter().all(|&entity| storage.is_alive(entity)));
+
+        assert_eq!(storage.reserve().unwrap(), EntityId::new(4, 0).unwrap());
+        assert_eq!(
+            storage.alloc_batch(1).unwrap(),
+            vec![EntityId::new(5, 0).unwrap()]
+        );
+        assert_eq!(storage.iter().count(), 5);
+    }
+
+    #[test]
+    fn alloc_exact() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        storage.dealloc(a).unwrap();
+
+        let a_v3 = EntityId::new(1, 3).unwrap();
+        let d = EntityId::new(4, 2).unwrap();
+        storage.alloc_exact(&[a_v3, d]).unwrap();
+
+        assert!(storage.is_alive(a_v3));
+        assert!(storage.is_alive(d));
+        assert!(!storage.is_alive(a));
+
+        // The skipped indices are free.
+        assert_eq!(storage.alloc().unwrap(), EntityId::new(3, 0).unwrap());
+        assert_eq!(storage.alloc().unwrap(), EntityId::new(2, 0).unwrap());
+        assert_eq!(storage.alloc().unwrap(), EntityId::new(5, 0).unwrap());
+    }
+
+    #[test]
+    fn alloc_exact_invalid() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        let b = storage.alloc().unwrap();
+        storage.dealloc(b).unwrap();
+        let reserved = storage.reserve().unwrap();
+
+        assert!(matches!(
+            storage.alloc_exact(&[a]),
+            Err(EntityError::AliveEntity)
+        ));
+
+        // The reserved entity is flushed first, so its index is in use.
+        assert!(matches!(
+            storage.alloc_exact(&[EntityId::new(2, 5).unwrap()]),
+            Err(EntityError::AliveEntity)
+        ));
+        assert!(storage.is_alive(reserved));
+
+        storage.dealloc(reserved).unwrap();
+        assert!(matches!(
+            storage.alloc_exact(&[EntityId::new(2, 1).unwrap()]),
+            Err(EntityError::StaleEntity)
+        ));
+
+        let c = EntityId::new(3, 0).unwrap();
+        assert!(matches!(
+            storage.alloc_exact(&[c, c]),
+            Err(EntityError::AliveEntity)
+        ));
+        assert!(!storage.is_alive(c));
+    }
+
+    #[test]
+    fn reserve() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        let b = storage.reserve().unwrap();
+        let c = storage.reserve().unwrap();
+
+        assert_eq!(b, EntityId::new(2, 0).unwrap());
+        assert_eq!(c, EntityId::new(3, 0).unwrap());
+        assert!(!storage.is_alive(b));
+
+        let d = storage.alloc().unwrap();
+        assert_eq!(d, EntityId::new(4, 0).unwrap());
+        assert!(storage.is_alive(a));
+        assert!(storage.is_alive(b));
+        assert!(storage.is_alive(c));
+    }
+
+    #[test]
+    fn reserve_free_indices() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        let b = storage.alloc().unwrap();
+        storage.dealloc(a).unwrap();
+        storage.dealloc(b).unwrap();
+
+        let b_v2 = storage.reserve().unwrap();
+        let a_v2 = storage.reserve().unwrap();
+        let c = storage.reserve().unwrap();
+
+        assert_eq!(b_v2, EntityId::new(2, 1).unwrap());
+        assert_eq!(a_v2, EntityId::new(1, 1).unwrap());
+        assert_eq!(c, EntityId::new(3, 0).unwrap());
+
+        assert!(!storage.is_alive(a_v2));
+        storage.flush_reserved();
+        assert!(storage.is_alive(a_v2));
+        assert!(storage.is_alive(b_v2));
+        assert!(storage.is_alive(c));
+        assert!(!storage.is_alive(a));
+
+        assert_eq!(storage.alloc().unwrap(), EntityId::new(4, 0).unwrap());
+    }
+
+    #[test]
+    fn reserve_some_free_indices() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        let b = storage.alloc().unwrap();
+        storage.dealloc(a).unwrap();
+        storage.dealloc(b).unwrap();
+
+        let b_v2 = storage.reserve().unwrap();
+        storage.flush_reserved();
+
+        assert!(storage.is_alive(b_v2));
+        assert_eq!(storage.alloc().unwrap(), EntityId::new(1, 1).unwrap());
+        assert_eq!(storage.alloc().unwrap(), EntityId::new(3, 0).unwrap());
+    }
+
+    #[test]
+    fn reserve_from_many_threads() {
+        let mut storage = EntityStorage::new();
+
+        let freed: Vec<_> = (0..8).map(|_| storage.alloc().unwrap()).collect();
+        for &entity in &freed {
+            storage.dealloc(entity).unwrap();
+        }
+
+        let mut reserved: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..16)
+                            .map(|_| storage.reserve().unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        storage.flush_reserved();
+        assert_eq!(storage.iter().count(), 64);
+        assert!(reserved.iter().all(|&entity| storage.is_alive(entity)));
+
+        reserved.sort_by_key(|entity| entity.index());
+        reserved.dedup_by_key(|entity| entity.index());
+        assert_eq!(reserved.len(), 64);
+    }
+
+    #[test]
+    fn alive_at() {
+        let mut storage = EntityStorage::new();
+
+        let a = storage.alloc().unwrap();
+        storage.dealloc(a).unwrap();
+        let a_v2 = storage.alloc().unwrap();
+
+        assert_eq!(storage.alive_at(a.index()), Some(a_v2));
+        assert_eq!(storage.alive_at(0), None);
+        assert_eq!(storage.alive_at(2), None);
+    }
+
     #[test]
     fn random_entities() {
         let mut storage = EntityStorage::new();
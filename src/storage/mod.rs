@@ -1,3 +1,5 @@
+pub mod bundle;
 pub mod component;
 pub mod entities;
+pub mod tick;
 pub mod unique;
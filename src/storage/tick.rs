@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A point in time for change detection.
+///
+/// The world's change tick advances every time a system is run with [`World::run`], and
+/// components remember the tick at which they were added and last changed.
+///
+/// [`World::run`]: crate::world::World::run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tick(u64);
+
+/// The world's change tick, which can be advanced through a shared reference.
+#[derive(Debug, Default)]
+pub(crate) struct AtomicTick(AtomicU64);
+
+impl AtomicTick {
+    #[inline]
+    pub fn get(&self) -> Tick {
+        Tick(self.0.load(Ordering::Acquire))
+    }
+
+    #[inline]
+    pub fn advance(&self) {
+        self.0.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ComponentTicks {
+    pub added: Tick,
+    pub changed: Tick,
+}
+
+impl ComponentTicks {
+    #[inline]
+    pub fn new(tick: Tick) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+}
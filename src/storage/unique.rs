@@ -1,5 +1,5 @@
 use std::any::Any;
 
-pub trait Unique: Any {}
+pub trait Unique: Any + Send + Sync {}
 
 pub(crate) struct UniqueStorage<T: Unique>(pub T);
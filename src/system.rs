@@ -1,8 +1,71 @@
-use crate::query::{Query, QueryResult};
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use crate::query::access::Access;
+use crate::query::{Query, QueryFamily, QueryResult};
 use crate::world::{World, WorldData};
 
 pub trait System<'a, Data: WorldData, Input, Output> {
     fn run(&self, world: &'a World<Data>) -> QueryResult<Output>;
+
+    /// Get the borrows this system makes when it runs.
+    fn access(&self) -> Access;
+}
+
+/// A system that has been type-erased so that it can be stored and run any number of
+/// times.
+pub struct BoxedSystem<Data: WorldData> {
+    system: Box<dyn ErasedSystem<Data>>,
+    name: &'static str,
+    access: Access,
+}
+
+impl<Data: WorldData> BoxedSystem<Data> {
+    #[inline]
+    pub fn run(&self, world: &World<Data>) -> QueryResult<()> {
+        self.system.run(world)
+    }
+
+    /// Borrow the system's queries, returning a task that runs the system with them.
+    ///
+    /// The task can be sent to another thread, so that systems can be borrowed in order on
+    /// one thread and then run in parallel.
+    #[inline]
+    pub fn borrow<'w>(&'w self, world: &'w World<Data>) -> QueryResult<SystemTask<'w>> {
+        self.system.borrow(world)
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[inline]
+    pub fn access(&self) -> &Access {
+        &self.access
+    }
+}
+
+/// A system whose queries have been borrowed, ready to be run.
+pub type SystemTask<'w> = Box<dyn FnOnce() + Send + 'w>;
+
+trait ErasedSystem<Data: WorldData>: Send + Sync {
+    fn run(&self, world: &World<Data>) -> QueryResult<()>;
+
+    fn borrow<'w>(&'w self, world: &'w World<Data>) -> QueryResult<SystemTask<'w>>;
+}
+
+/// Conversion of a function into a [`BoxedSystem`].
+///
+/// This is implemented for thread-safe functions whose parameters are all [`QueryFamily`]
+/// types.
+pub trait IntoBoxedSystem<Data: WorldData, Input, Output> {
+    fn into_boxed(self) -> BoxedSystem<Data>;
+}
+
+struct FunctionSystem<Func, Input> {
+    func: Func,
+    _marker: PhantomData<fn() -> Input>,
 }
 
 macro_rules! impl_system {
@@ -21,6 +84,67 @@ macro_rules! impl_system {
                 let output = (self)($($query,)*);
                 Ok(output)
             }
+
+            #[allow(unused_mut)]
+            fn access(&self) -> Access {
+                let mut access = Access::new();
+                $($query::access(&mut access);)*
+                access
+            }
+        }
+
+        impl<Func, Data, $($query,)* Output>
+            IntoBoxedSystem<Data, ($($query,)*), Output>
+            for Func
+            where
+                Func: Fn($($query),*) -> Output
+                    + for<'a> Fn($($query::Item<'a>),*) -> Output
+                    + Send
+                    + Sync
+                    + 'static,
+                Data: WorldData,
+                $($query: QueryFamily<Data> + 'static),*
+        {
+            #[allow(unused_mut)]
+            fn into_boxed(self) -> BoxedSystem<Data> {
+                let system = FunctionSystem {
+                    func: self,
+                    _marker: PhantomData::<fn() -> ($($query,)*)>,
+                };
+
+                let mut access = Access::new();
+                $(<$query::Item<'static> as Query<'static, Data>>::access(&mut access);)*
+
+                BoxedSystem {
+                    system: Box::new(system),
+                    name: type_name::<Func>(),
+                    access,
+                }
+            }
+        }
+
+        impl<Func, Data, $($query,)* Output>
+            ErasedSystem<Data>
+            for FunctionSystem<Func, ($($query,)*)>
+            where
+                Func: for<'a> Fn($($query::Item<'a>),*) -> Output + Send + Sync,
+                Data: WorldData,
+                $($query: QueryFamily<Data>),*
+        {
+            #[allow(unused_variables, non_snake_case)]
+            fn run(&self, world: &World<Data>) -> QueryResult<()> {
+                $(let $query = <$query::Item<'_> as Query<'_, Data>>::borrow(world)?;)*
+                (self.func)($($query,)*);
+                Ok(())
+            }
+
+            #[allow(unused_variables, non_snake_case)]
+            fn borrow<'w>(&'w self, world: &'w World<Data>) -> QueryResult<SystemTask<'w>> {
+                $(let $query = <$query::Item<'w> as Query<'w, Data>>::borrow(world)?;)*
+                Ok(Box::new(move || {
+                    (self.func)($($query,)*);
+                }))
+            }
         }
     }
 }
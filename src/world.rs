@@ -1,20 +1,34 @@
 use crate::entity_mut::EntityMut;
-use std::cell::RefCell;
+use crate::entity_ref::EntityRef;
+use atomic_refcell::AtomicRefCell;
 
 use crate::erased_storages::AllStorages;
-use crate::query::{Query, QueryResult};
-use crate::storage::entities::EntityError;
+use crate::query::{Query, QueryError, QueryResult};
+use crate::registry::{ComponentConfig, Registry, UniqueConfig};
+use crate::storage::bundle::Bundle;
+use crate::storage::component::{Component, ComponentInfo};
+use crate::storage::entities::{EntityError, EntityId};
+use crate::storage::tick::Tick;
 use crate::storage::unique::{Unique, UniqueStorage};
 use crate::system::System;
 
-pub trait WorldData: Default + 'static {}
+pub trait WorldData: Default + Send + Sync + 'static {}
 
 impl WorldData for () {}
 
+/// The world, containing all entities, components and uniques.
+///
+/// A world can be shared between threads. Each storage has an atomic reader/writer borrow
+/// flag, so a conflicting borrow fails with [`QueryError::BorrowError`] or
+/// [`QueryError::BorrowMutError`] no matter which thread holds the other borrow.
+///
+/// [`QueryError::BorrowError`]: crate::query::QueryError::BorrowError
+/// [`QueryError::BorrowMutError`]: crate::query::QueryError::BorrowMutError
 #[derive(Default)]
 pub struct World<D: WorldData = ()> {
     pub(crate) all_storages: AllStorages,
-    pub data: RefCell<D>,
+    pub(crate) registry: Registry,
+    pub data: AtomicRefCell<D>,
 }
 
 impl<Data: WorldData> World<Data> {
@@ -24,7 +38,7 @@ impl<Data: WorldData> World<Data> {
     }
 
     #[inline]
-    pub fn spawn(&mut self) -> Result<EntityMut, EntityError> {
+    pub fn spawn(&mut self) -> Result<EntityMut<'_>, EntityError> {
         let entity = self.all_storages.entities.alloc()?;
         Ok(EntityMut {
             all_storages: &mut self.all_storages,
@@ -32,20 +46,267 @@ impl<Data: WorldData> World<Data> {
         })
     }
 
+    /// Spawn an entity with every component in a bundle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bundle contains the same component type more than once.
+    pub fn spawn_with<B: Bundle>(&mut self, bundle: B) -> Result<EntityMut<'_>, EntityError> {
+        self.check_bundle::<B>();
+        let entity = self
+            .spawn()?
+            .insert_bundle(bundle)
+            .expect("bundle storages should be borrowable");
+        Ok(entity)
+    }
+
+    /// Spawn an entity for every bundle in an iterator, returning their ids in order.
+    ///
+    /// This is faster than calling [`spawn_with`](Self::spawn_with) in a loop: the entities
+    /// are allocated together, space for their components is reserved up front, and each
+    /// storage is only borrowed once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bundle type contains the same component type more than once.
+    pub fn spawn_batch<B, I>(&mut self, bundles: I) -> Result<Vec<EntityId>, EntityError>
+    where
+        B: Bundle,
+        I: IntoIterator<Item = B>,
+    {
+        self.check_bundle::<B>();
+        let bundles: Vec<B> = bundles.into_iter().collect();
+        let entities = self.all_storages.entities.alloc_batch(bundles.len())?;
+
+        let mut storages =
+            B::borrow_storages(&self.all_storages).expect("bundle storages should be borrowable");
+        B::reserve(&mut storages, &entities);
+
+        for (bundle, &entity) in bundles.into_iter().zip(&entities) {
+            bundle
+                .insert(&mut storages, entity)
+                .expect("entity should be alive");
+        }
+
+        Ok(entities)
+    }
+
+    /// Panic if a bundle's storages can't all be borrowed at once, before any entities are
+    /// allocated for it.
+    fn check_bundle<B: Bundle>(&self) {
+        let storages = B::borrow_storages(&self.all_storages)
+            .expect("bundle should not contain a component type more than once");
+        drop(storages);
+    }
+
+    /// Get read-only access to an entity's components.
+    #[inline]
+    pub fn entity(&self, entity: EntityId) -> Result<EntityRef<'_>, EntityError> {
+        if !self.all_storages.entities.is_alive(entity) {
+            return Err(EntityError::DeadEntity);
+        }
+
+        Ok(EntityRef {
+            all_storages: &self.all_storages,
+            entity,
+        })
+    }
+
+    /// Get mutable access to an entity's components.
+    #[inline]
+    pub fn entity_mut(&mut self, entity: EntityId) -> Result<EntityMut<'_>, EntityError> {
+        if !self.all_storages.entities.is_alive(entity) {
+            return Err(EntityError::DeadEntity);
+        }
+
+        Ok(EntityMut {
+            all_storages: &mut self.all_storages,
+            entity,
+        })
+    }
+
+    /// List the components that an entity has, sorted by type name.
+    pub fn components_of(&self, entity: EntityId) -> QueryResult<Vec<ComponentInfo>> {
+        if !self.all_storages.entities.is_alive(entity) {
+            return Err(QueryError::EntityDead);
+        }
+
+        let mut infos = vec![];
+        for storage in self.all_storages.components.iter_ref() {
+            let storage = storage?;
+            if storage.contains(entity) {
+                infos.push(storage.info());
+            }
+        }
+
+        infos.sort_by_key(|info| info.name);
+        Ok(infos)
+    }
+
+    /// Spawn a copy of an entity, cloning every component whose type was registered with
+    /// [`cloneable`](crate::registry::ComponentConfig::cloneable).
+    ///
+    /// Returns the new entity, along with the components that weren't cloneable and so
+    /// were skipped, sorted by type name.
+    pub fn clone_entity(
+        &mut self,
+        entity: EntityId,
+    ) -> Result<(EntityId, Vec<ComponentInfo>), EntityError> {
+        if !self.all_storages.entities.is_alive(entity) {
+            return Err(EntityError::DeadEntity);
+        }
+
+        let clone = self.all_storages.entities.alloc()?;
+
+        let registrations = &self.registry.components;
+        for component_clone in registrations.iter().filter_map(|r| r.clone.as_ref()) {
+            component_clone.clone_component(&mut self.all_storages, entity, clone);
+        }
+
+        let mut skipped: Vec<ComponentInfo> = self
+            .all_storages
+            .components
+            .iter_mut()
+            .filter(|storage| storage.contains(entity))
+            .map(|storage| storage.info())
+            .filter(|info| {
+                !registrations.iter().any(|registration| {
+                    registration.type_id == info.type_id && registration.clone.is_some()
+                })
+            })
+            .collect();
+
+        skipped.sort_by_key(|info| info.name);
+        Ok((clone, skipped))
+    }
+
+    /// Reserve an entity without needing mutable access to the world.
+    ///
+    /// The entity becomes alive when [`flush_reserved_entities`] or
+    /// [`apply_commands`] is called, or when another entity is spawned.
+    ///
+    /// [`flush_reserved_entities`]: Self::flush_reserved_entities
+    /// [`apply_commands`]: Self::apply_commands
     #[inline]
-    pub fn insert_unique<T: Unique>(&mut self, unique: T) {
-        self.all_storages.uniques.insert(UniqueStorage(unique));
+    pub fn reserve_entity(&self) -> Result<EntityId, EntityError> {
+        self.all_storages.entities.reserve()
+    }
+
+    /// Make all reserved entities alive.
+    #[inline]
+    pub fn flush_reserved_entities(&mut self) {
+        self.all_storages.entities.flush_reserved();
+    }
+
+    /// Despawn an entity, removing all of its components.
+    #[inline]
+    pub fn despawn(&mut self, entity: EntityId) -> Result<(), EntityError> {
+        self.all_storages.despawn(entity)
+    }
+
+    /// Apply the commands recorded by [`Commands`] queries.
+    ///
+    /// Commands are applied in the order they were recorded. If a command fails because
+    /// its entity is dead, the remaining commands are still applied and the first error
+    /// is returned.
+    ///
+    /// [`Commands`]: crate::query::commands::Commands
+    pub fn apply_commands(&mut self) -> Result<(), EntityError> {
+        self.all_storages.apply_commands()
+    }
+
+    /// Forget all recorded component removals.
+    ///
+    /// Removals are kept until this is called, so it should be called regularly (for
+    /// example once per frame) after every reader has had a chance to see them.
+    pub fn clear_removed_components(&mut self) {
+        self.all_storages.removed.clear();
+    }
+
+    /// Insert a unique, returning the one it replaced, if any.
+    #[inline]
+    pub fn insert_unique<T: Unique>(&mut self, unique: T) -> Option<T> {
+        self.all_storages
+            .uniques
+            .insert(UniqueStorage(unique))
+            .map(|storage| storage.0)
+    }
+
+    /// Insert a unique's default value, unless the unique already exists.
+    #[inline]
+    pub fn init_unique<T: Unique + Default>(&mut self) {
+        if !self.contains_unique::<T>() {
+            self.insert_unique(T::default());
+        }
+    }
+
+    /// Remove a unique and return it, if it exists.
+    #[inline]
+    pub fn remove_unique<T: Unique>(&mut self) -> Option<T> {
+        self.all_storages
+            .uniques
+            .remove::<UniqueStorage<T>>()
+            .map(|storage| storage.0)
+    }
+
+    #[inline]
+    pub fn contains_unique<T: Unique>(&self) -> bool {
+        self.all_storages.uniques.contains::<UniqueStorage<T>>()
+    }
+
+    /// Register a component type under a stable name, returning a [`ComponentConfig`]
+    /// that says what can be done with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is taken by another type, or the type is already registered
+    /// under another name.
+    #[inline]
+    pub fn register_component<C: Component>(
+        &mut self,
+        name: &'static str,
+    ) -> ComponentConfig<'_, C> {
+        self.registry.register_component(name)
+    }
+
+    /// Register a unique type under a stable name, returning a [`UniqueConfig`] that says
+    /// what can be done with it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is taken by another type, or the type is already registered
+    /// under another name.
+    #[inline]
+    pub fn register_unique<T: Unique>(&mut self, name: &'static str) -> UniqueConfig<'_, T> {
+        self.registry.register_unique(name)
     }
 
     pub fn borrow<'a, Q: Query<'a, Data>>(&'a self) -> QueryResult<Q> {
         Q::borrow(self)
     }
 
+    /// Get the current change tick.
+    ///
+    /// Components that are added or changed from now on will be marked with this tick
+    /// or a later one.
+    #[inline]
+    pub fn change_tick(&self) -> Tick {
+        self.all_storages.change_tick.get()
+    }
+
+    /// Run a system, then advance the change tick.
     #[inline]
     pub fn run<'a, S: System<'a, Data, Input, Output>, Input, Output>(
         &'a self,
         system: S,
     ) -> QueryResult<Output> {
-        system.run(self)
+        let output = system.run(self);
+        self.advance_change_tick();
+        output
+    }
+
+    #[inline]
+    pub(crate) fn advance_change_tick(&self) {
+        self.all_storages.change_tick.advance();
     }
 }
@@ -0,0 +1,69 @@
+use std::any::TypeId;
+
+use ecs2::prelude::*;
+use ecs2::query::access::{Access, AccessKind, Resource};
+
+struct Foo;
+impl Component for Foo {}
+
+struct Bar;
+impl Component for Bar {}
+
+struct Baz;
+impl Unique for Baz {}
+
+fn access_of<'a, Q: Query<'a, ()>>() -> Access {
+    let mut access = Access::new();
+    Q::access(&mut access);
+    access
+}
+
+#[test]
+fn query_access() {
+    let access = access_of::<(QueryComp<Foo>, QueryCompMut<Bar>, QueryUnique<Baz>)>();
+    let accesses: Vec<_> = access.iter().map(|a| (a.resource, a.kind)).collect();
+
+    assert_eq!(
+        accesses,
+        [
+            (Resource::Component(TypeId::of::<Foo>()), AccessKind::Read),
+            (Resource::Component(TypeId::of::<Bar>()), AccessKind::Write),
+            (Resource::Unique(TypeId::of::<Baz>()), AccessKind::Read),
+        ]
+    );
+    access.validate().unwrap();
+}
+
+#[test]
+fn shared_reads_dont_conflict() {
+    access_of::<(QueryComp<Foo>, QueryComp<Foo>, RemovedComponents<Foo>)>()
+        .validate()
+        .unwrap();
+}
+
+#[test]
+fn writes_conflict() {
+    access_of::<(QueryComp<Foo>, QueryCompMut<Foo>)>()
+        .validate()
+        .unwrap_err();
+    access_of::<(QueryUniqueMut<Baz>, QueryUnique<Baz>)>()
+        .validate()
+        .unwrap_err();
+}
+
+#[test]
+fn conflict_message() {
+    let error = access_of::<(QueryCompMut<Foo>, QueryCompMut<Foo>)>()
+        .validate()
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "conflicting access: writes component `access::Foo` and writes component `access::Foo`"
+    );
+}
+
+#[test]
+fn commands_dont_borrow() {
+    assert_eq!(access_of::<(Commands, Commands)>().iter().count(), 0);
+}
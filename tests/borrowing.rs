@@ -41,3 +41,27 @@ fn add_and_borrow_unique() {
         "hello, world! how are you?"
     ));
 }
+
+#[test]
+fn borrow_tuple() {
+    let mut world = World::<()>::new();
+
+    let entity = world.spawn().unwrap().insert(MyCmp(12)).unwrap().id();
+    world.insert_unique(MyUnique("hello".to_owned()));
+
+    let (my_cmps, my_unique) = world
+        .borrow::<(QueryComp<MyCmp>, QueryUnique<MyUnique>)>()
+        .unwrap();
+    assert!(matches!(my_cmps.get(entity), Ok(MyCmp(12))));
+    assert_eq!(my_unique.get().0.as_str(), "hello");
+}
+
+#[test]
+fn borrow_tuple_conflict() {
+    let world = World::<()>::new();
+
+    assert!(matches!(
+        world.borrow::<(QueryComp<MyCmp>, QueryCompMut<MyCmp>)>(),
+        Err(QueryError::BorrowMutError(_))
+    ));
+}
@@ -0,0 +1,91 @@
+use ecs2::prelude::*;
+
+#[derive(Debug, PartialEq)]
+struct Pos(i32);
+impl Component for Pos {}
+
+#[derive(Debug, PartialEq)]
+struct Vel(i32);
+impl Component for Vel {}
+
+#[derive(Debug, PartialEq)]
+struct Health(u32);
+impl Component for Health {}
+
+#[test]
+fn spawn_with_tuple() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with((Pos(1), Vel(2))).unwrap().id();
+
+    let (pos, vel) = world.borrow::<(QueryComp<Pos>, QueryComp<Vel>)>().unwrap();
+    assert_eq!(pos.get(a).unwrap(), &Pos(1));
+    assert_eq!(vel.get(a).unwrap(), &Vel(2));
+}
+
+#[test]
+fn nested_tuples() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn_with(((Pos(1), Vel(2)), Health(3)))
+        .unwrap()
+        .id();
+
+    let health = world.borrow::<QueryComp<Health>>().unwrap();
+    assert_eq!(health.get(a).unwrap(), &Health(3));
+}
+
+#[test]
+fn insert_and_remove_bundle() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Health(10))
+        .unwrap()
+        .insert_bundle((Pos(1), Vel(2)))
+        .unwrap()
+        .remove_bundle::<(Pos, Vel)>()
+        .unwrap()
+        .id();
+
+    let (pos, vel, health) = world
+        .borrow::<(QueryComp<Pos>, QueryComp<Vel>, QueryComp<Health>)>()
+        .unwrap();
+    assert!(pos.get(a).is_err());
+    assert!(vel.get(a).is_err());
+    assert_eq!(health.get(a).unwrap(), &Health(10));
+}
+
+#[test]
+fn remove_partial_bundle() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn_with(Pos(1))
+        .unwrap()
+        .remove_bundle::<(Pos, Vel)>()
+        .unwrap()
+        .id();
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert!(pos.get(a).is_err());
+}
+
+#[test]
+#[should_panic]
+fn duplicate_component_types() {
+    let mut world = World::<()>::new();
+    let _ = world.spawn_with((Pos(1), Pos(2)));
+}
+
+#[test]
+fn duplicate_component_types_dont_leak_entities() {
+    let mut world = World::<()>::new();
+    let spawned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = world.spawn_with((Pos(1), Pos(2)));
+    }));
+    assert!(spawned.is_err());
+
+    // The entity is allocated only once the bundle is known to be valid.
+    let expected = World::<()>::new().spawn().unwrap().id();
+    assert_eq!(world.spawn().unwrap().id(), expected);
+}
@@ -0,0 +1,57 @@
+use ecs2::prelude::*;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+#[test]
+fn added_since() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    world.run(|| {}).unwrap();
+
+    let tick = world.change_tick();
+    let b = world.spawn().unwrap().insert(Foo(2)).unwrap().id();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    let added: Vec<_> = foos.iter_added_since(tick).collect();
+    assert_eq!(added, vec![(b, &Foo(2))]);
+
+    let all: Vec<_> = foos.iter_added_since(Default::default()).collect();
+    assert_eq!(all, vec![(a, &Foo(1)), (b, &Foo(2))]);
+}
+
+#[test]
+fn changed_only_when_mutated() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    let b = world.spawn().unwrap().insert(Foo(2)).unwrap().id();
+    world.run(|| {}).unwrap();
+
+    let tick = world.change_tick();
+    world
+        .run(|mut foos: QueryCompMut<Foo>| {
+            // Only reading through `Mut` doesn't mark the component as changed.
+            assert_eq!(*foos.get_mut(a).unwrap(), Foo(1));
+            foos.get_mut(b).unwrap().0 += 1;
+        })
+        .unwrap();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    let changed: Vec<_> = foos.iter_changed_since(tick).collect();
+    assert_eq!(changed, vec![(b, &Foo(3))]);
+    assert_eq!(foos.iter_added_since(tick).count(), 0);
+}
+
+#[test]
+fn change_tick_advances_per_run() {
+    let world = World::<()>::new();
+
+    let before = world.change_tick();
+    world.run(|| {}).unwrap();
+    world.run(|| {}).unwrap();
+
+    assert!(world.change_tick() > before);
+}
@@ -0,0 +1,66 @@
+use ecs2::prelude::*;
+use ecs2::storage::component::ComponentInfo;
+use ecs2::storage::entities::EntityError;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pos(i32);
+impl Component for Pos {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Vel(i32);
+impl Component for Vel {}
+
+#[derive(Debug, PartialEq)]
+struct Handle(u32);
+impl Component for Handle {}
+
+#[test]
+fn clone_entity() {
+    let mut world: World = World::new();
+    world.register_component::<Pos>("pos").cloneable();
+    world.register_component::<Vel>("vel").diffable();
+    world.register_component::<Handle>("handle");
+
+    let a = world.spawn_with((Pos(1), Vel(2), Handle(3))).unwrap().id();
+    let (b, skipped) = world.clone_entity(a).unwrap();
+
+    assert_ne!(a, b);
+    assert_eq!(skipped, vec![ComponentInfo::of::<Handle>()]);
+
+    let b = world.entity(b).unwrap();
+    assert_eq!(*b.get::<Pos>().unwrap(), Pos(1));
+    assert_eq!(*b.get::<Vel>().unwrap(), Vel(2));
+    assert!(!b.contains::<Handle>());
+
+    // The original is untouched.
+    let a = world.entity(a).unwrap();
+    assert_eq!(*a.get::<Pos>().unwrap(), Pos(1));
+    assert_eq!(*a.get::<Handle>().unwrap(), Handle(3));
+}
+
+#[test]
+fn clone_entity_with_unregistered_components() {
+    let mut world: World = World::new();
+    world.register_component::<Vel>("vel").cloneable();
+
+    let a = world.spawn_with((Pos(1), Handle(2))).unwrap().id();
+    let (b, skipped) = world.clone_entity(a).unwrap();
+
+    assert_eq!(
+        skipped,
+        vec![ComponentInfo::of::<Handle>(), ComponentInfo::of::<Pos>()]
+    );
+    assert!(world.components_of(b).unwrap().is_empty());
+}
+
+#[test]
+fn clone_dead_entity() {
+    let mut world: World = World::new();
+    let a = world.spawn().unwrap().id();
+    world.despawn(a).unwrap();
+
+    assert!(matches!(
+        world.clone_entity(a),
+        Err(EntityError::DeadEntity)
+    ));
+}
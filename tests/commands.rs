@@ -0,0 +1,133 @@
+use ecs2::prelude::*;
+use ecs2::storage::entities::EntityError;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Bar(usize);
+impl Component for Bar {}
+
+struct Score(usize);
+impl Unique for Score {}
+
+#[test]
+fn spawn_and_insert() {
+    let mut world = World::<()>::new();
+
+    let spawned = world
+        .run(|mut commands: Commands, foos: QueryComp<Foo>| {
+            let entity = commands.spawn().unwrap();
+            commands.insert(entity, Foo(1));
+            commands.insert_unique(Score(foos.iter().count()));
+
+            // Nothing happens until the commands are applied.
+            assert!(foos.get(entity).is_err());
+            entity
+        })
+        .unwrap();
+
+    world.apply_commands().unwrap();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    assert_eq!(foos.get(spawned).unwrap(), &Foo(1));
+    assert_eq!(world.borrow::<QueryUnique<Score>>().unwrap().get().0, 0);
+}
+
+#[test]
+fn despawn_and_remove() {
+    let mut world = World::<()>::new();
+
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Foo(1))
+        .unwrap()
+        .insert(Bar(2))
+        .unwrap()
+        .id();
+    let b = world.spawn().unwrap().insert(Foo(3)).unwrap().id();
+
+    world
+        .run(|mut commands: Commands| {
+            commands.remove::<Bar>(a);
+            commands.despawn(b);
+        })
+        .unwrap();
+    world.apply_commands().unwrap();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    let bars = world.borrow::<QueryComp<Bar>>().unwrap();
+    assert_eq!(foos.get(a).unwrap(), &Foo(1));
+    assert!(bars.get(a).is_err());
+    assert!(foos.get(b).is_err());
+}
+
+#[test]
+fn spawned_entities_dont_collide() {
+    let mut world = World::<()>::new();
+
+    let reserved = world
+        .run(|mut commands: Commands| commands.spawn().unwrap())
+        .unwrap();
+    let allocated = world.spawn().unwrap().id();
+
+    assert_ne!(reserved, allocated);
+    world.apply_commands().unwrap();
+
+    world.despawn(reserved).unwrap();
+    world.despawn(allocated).unwrap();
+}
+
+#[test]
+fn failed_commands_dont_stop_others() {
+    let mut world = World::<()>::new();
+
+    let dead = world.spawn().unwrap().id();
+    world.despawn(dead).unwrap();
+    let alive = world.spawn().unwrap().id();
+
+    world
+        .run(|mut commands: Commands| {
+            commands.insert(dead, Foo(1));
+            commands.insert(alive, Foo(2));
+        })
+        .unwrap();
+
+    assert!(matches!(
+        world.apply_commands(),
+        Err(EntityError::DeadEntity)
+    ));
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    assert_eq!(foos.get(alive).unwrap(), &Foo(2));
+}
+
+#[test]
+fn reserve_through_shared_world() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().id();
+    world.despawn(a).unwrap();
+
+    let reused = world.reserve_entity().unwrap();
+    let fresh = world.reserve_entity().unwrap();
+    assert!(world
+        .borrow::<QueryComp<Foo>>()
+        .unwrap()
+        .get(reused)
+        .is_err());
+
+    world.flush_reserved_entities();
+    world
+        .run(|mut foos: QueryCompMut<Foo>| {
+            foos.insert(reused, Foo(1)).unwrap();
+            foos.insert(fresh, Foo(2)).unwrap();
+        })
+        .unwrap();
+
+    assert!(world.despawn(a).is_err());
+    world.despawn(reused).unwrap();
+    world.despawn(fresh).unwrap();
+}
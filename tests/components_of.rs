@@ -0,0 +1,57 @@
+use std::any::TypeId;
+
+use ecs2::prelude::*;
+use ecs2::query::QueryError;
+use ecs2::storage::component::ComponentInfo;
+
+#[allow(dead_code)]
+struct Pos(i32, i32);
+impl Component for Pos {}
+
+#[allow(dead_code)]
+struct Vel(i32, i32);
+impl Component for Vel {}
+
+struct Marker;
+impl Component for Marker {}
+
+#[test]
+fn components_of() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with((Vel(1, 1), Pos(0, 0))).unwrap().id();
+    let b = world.spawn_with(Marker).unwrap().id();
+    let empty = world.spawn().unwrap().id();
+
+    assert_eq!(
+        world.components_of(a).unwrap(),
+        vec![ComponentInfo::of::<Pos>(), ComponentInfo::of::<Vel>()]
+    );
+
+    assert_eq!(world.components_of(a).unwrap()[0].size, 8);
+
+    let infos = world.components_of(b).unwrap();
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].name, std::any::type_name::<Marker>());
+    assert_eq!(infos[0].type_id, TypeId::of::<Marker>());
+    assert_eq!(infos[0].size, 0);
+
+    assert!(world.components_of(empty).unwrap().is_empty());
+
+    world.despawn(a).unwrap();
+    assert!(matches!(
+        world.components_of(a),
+        Err(QueryError::EntityDead)
+    ));
+}
+
+#[test]
+fn components_of_borrowed_storage() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(0, 0)).unwrap().id();
+
+    let _pos = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    assert!(matches!(
+        world.components_of(a),
+        Err(QueryError::BorrowError(_))
+    ));
+}
@@ -1,7 +1,7 @@
-use std::cell::RefMut;
-
 use ecs2::prelude::*;
-use ecs2::query::QueryResult;
+use ecs2::query::access::Access;
+use ecs2::query::{AtomicRefMut, QueryFamily, QueryResult};
+use ecs2::schedule::ScheduleError;
 
 #[derive(Default)]
 pub struct GameInfo {
@@ -10,12 +10,16 @@ pub struct GameInfo {
 
 impl WorldData for GameInfo {}
 
-pub struct QueryGameInfo<'a>(RefMut<'a, GameInfo>);
+pub struct QueryGameInfo<'a>(AtomicRefMut<'a, GameInfo>);
 
 impl<'a> Query<'a, GameInfo> for QueryGameInfo<'a> {
     fn borrow(world: &'a World<GameInfo>) -> QueryResult<Self> {
         Ok(QueryGameInfo(world.data.try_borrow_mut()?))
     }
+
+    fn access(access: &mut Access) {
+        access.write_data();
+    }
 }
 
 #[test]
@@ -26,3 +30,50 @@ fn custom_query() {
     game_info.0.name.push_str("foobar");
     assert_eq!(game_info.0.name.as_str(), "foobar");
 }
+
+pub struct Named(&'static str);
+
+impl Component for Named {}
+
+pub struct QueryNamedCmps<'a> {
+    info: QueryGameInfo<'a>,
+    cmps: QueryComp<'a, Named>,
+}
+
+impl<'a> Query<'a, GameInfo> for QueryNamedCmps<'a> {
+    fn borrow(world: &'a World<GameInfo>) -> QueryResult<Self> {
+        let (info, cmps) = world.borrow()?;
+        Ok(QueryNamedCmps { info, cmps })
+    }
+
+    fn access(access: &mut Access) {
+        QueryGameInfo::access(access);
+        <QueryComp<Named> as Query<GameInfo>>::access(access);
+    }
+}
+
+impl QueryFamily<GameInfo> for QueryNamedCmps<'_> {
+    type Item<'a> = QueryNamedCmps<'a>;
+}
+
+#[test]
+fn composed_query() {
+    let mut world = World::<GameInfo>::new();
+    world.spawn().unwrap().insert(Named("foo")).unwrap();
+
+    let mut named = world.borrow::<QueryNamedCmps>().unwrap();
+    for cmp in named.cmps.iter() {
+        named.info.0.name.push_str(cmp.0);
+    }
+    assert_eq!(named.info.0.name.as_str(), "foo");
+}
+
+#[test]
+fn composed_query_conflict_is_rejected() {
+    let mut schedule = Schedule::<GameInfo>::new();
+
+    assert!(matches!(
+        schedule.add_system(|_named: QueryNamedCmps, _cmps: QueryCompMut<Named>| {}),
+        Err(ScheduleError::Conflict { .. })
+    ));
+}
@@ -0,0 +1,189 @@
+use ecs2::prelude::*;
+use ecs2::storage::entities::EntityError;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pos(i32);
+impl Component for Pos {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Name(&'static str);
+impl Component for Name {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Local(i32);
+impl Component for Local {}
+
+fn registered_world() -> World {
+    let mut world = World::new();
+    world.register_component::<Pos>("pos").diffable();
+    world.register_component::<Name>("name").diffable();
+    world.register_component::<Local>("local").cloneable();
+    world
+}
+
+#[test]
+fn diff() {
+    let mut world = registered_world();
+    let a = world.spawn_with((Pos(0), Name("a"))).unwrap().id();
+    let b = world.spawn_with(Pos(1)).unwrap().id();
+    let c = world.spawn_with(Pos(2)).unwrap().id();
+    let old = world.snapshot().unwrap();
+
+    world.entity_mut(a).unwrap().insert(Pos(5)).unwrap();
+    world.entity_mut(b).unwrap().insert(Pos(1)).unwrap();
+    world.entity_mut(b).unwrap().insert(Name("b")).unwrap();
+    world.entity_mut(a).unwrap().remove::<Name>().unwrap();
+    world.despawn(c).unwrap();
+    let d = world.spawn_with((Pos(3), Local(0))).unwrap().id();
+    let new = world.snapshot().unwrap();
+
+    let delta = old.diff(&new);
+    assert_eq!(delta.spawned(), &[d]);
+    assert_eq!(delta.despawned(), &[c]);
+
+    // Setting a component to an equal value isn't a change.
+    let pos = delta.components::<Pos>().unwrap();
+    assert_eq!(pos.added(), &[(d, Pos(3))]);
+    assert_eq!(pos.changed(), &[(a, Pos(5))]);
+    assert!(pos.removed().is_empty());
+
+    let name = delta.components::<Name>().unwrap();
+    assert_eq!(name.added(), &[(b, Name("b"))]);
+    assert!(name.changed().is_empty());
+    assert_eq!(name.removed(), &[a]);
+
+    // Components that are only cloneable aren't diffed.
+    assert!(delta.components::<Local>().is_none());
+
+    assert!(new.diff(&new).is_empty());
+}
+
+#[test]
+fn replicate() {
+    let mut server = registered_world();
+    let mut client = registered_world();
+    let mut last = server.snapshot().unwrap();
+
+    let mut sync = |server: &World, client: &mut World| {
+        let snapshot = server.snapshot().unwrap();
+        client.apply_delta(&last.diff(&snapshot)).unwrap();
+        last = snapshot;
+    };
+
+    let a = server.spawn_with(Pos(0)).unwrap().id();
+    let b = server.spawn_with((Pos(1), Name("b"))).unwrap().id();
+    sync(&server, &mut client);
+
+    assert_eq!(*client.entity(a).unwrap().get::<Pos>().unwrap(), Pos(0));
+    assert_eq!(*client.entity(b).unwrap().get::<Name>().unwrap(), Name("b"));
+
+    // Reusing an index gives a new version, which the client has to match.
+    server.despawn(a).unwrap();
+    let c = server.spawn_with(Pos(2)).unwrap().id();
+    server.entity_mut(b).unwrap().remove::<Name>().unwrap();
+    sync(&server, &mut client);
+
+    assert!(client.entity(a).is_err());
+    assert_eq!(*client.entity(c).unwrap().get::<Pos>().unwrap(), Pos(2));
+    assert!(!client.entity(b).unwrap().contains::<Name>());
+
+    let removed = client.borrow::<RemovedComponents<Name>>().unwrap();
+    assert_eq!(removed.iter().collect::<Vec<_>>(), vec![b]);
+
+    // Entities whose ids were skipped on the client can still be spawned later.
+    let skipped = server.spawn().unwrap().id();
+    let d = server.spawn_with(Pos(3)).unwrap().id();
+    server.despawn(skipped).unwrap();
+    sync(&server, &mut client);
+
+    assert_eq!(*client.entity(d).unwrap().get::<Pos>().unwrap(), Pos(3));
+    assert!(client.entity(skipped).is_err());
+
+    let e = server.spawn_with(Pos(4)).unwrap().id();
+    sync(&server, &mut client);
+    assert_eq!(*client.entity(e).unwrap().get::<Pos>().unwrap(), Pos(4));
+}
+
+#[test]
+fn apply_to_mismatched_world() {
+    let mut world = registered_world();
+    let old = world.snapshot().unwrap();
+    let a = world.spawn_with(Pos(0)).unwrap().id();
+    let new = world.snapshot().unwrap();
+
+    // `a` is already alive, so it can't be spawned again.
+    assert!(matches!(
+        world.apply_delta(&old.diff(&new)),
+        Err(EntityError::AliveEntity)
+    ));
+
+    // `a` isn't alive in this world, so it can't be despawned.
+    let mut other = registered_world();
+    assert!(matches!(
+        other.apply_delta(&new.diff(&old)),
+        Err(EntityError::DeadEntity)
+    ));
+    assert!(other.entity(a).is_err());
+}
+
+#[test]
+fn apply_over_reserved_entity() {
+    let mut server = registered_world();
+    let old = server.snapshot().unwrap();
+    server.spawn_with(Pos(0)).unwrap();
+    let delta = old.diff(&server.snapshot().unwrap());
+
+    // The reserved entity takes the same index, so the delta can't be applied.
+    let mut client = registered_world();
+    let reserved = client.reserve_entity().unwrap();
+    assert_eq!(delta.spawned(), &[reserved]);
+    assert!(matches!(
+        client.apply_delta(&delta),
+        Err(EntityError::AliveEntity)
+    ));
+    assert!(client.components_of(reserved).unwrap().is_empty());
+}
+
+#[test]
+fn apply_stale_entity() {
+    let mut server = registered_world();
+    let old = server.snapshot().unwrap();
+    server.spawn_with(Pos(0)).unwrap();
+    let delta = old.diff(&server.snapshot().unwrap());
+
+    // The client has already used later versions of the index.
+    let mut client = registered_world();
+    let mut stale = vec![];
+    for _ in 0..3 {
+        let entity = client.spawn().unwrap().id();
+        client.despawn(entity).unwrap();
+        stale.push(entity);
+    }
+
+    assert_eq!(delta.spawned(), &[stale[0]]);
+    assert!(matches!(
+        client.apply_delta(&delta),
+        Err(EntityError::StaleEntity)
+    ));
+    assert!(client.entity(stale[0]).is_err());
+}
+
+#[test]
+fn apply_components_of_dead_entity() {
+    let mut server = registered_world();
+    let a = server.spawn().unwrap().id();
+    let old = server.snapshot().unwrap();
+    server.entity_mut(a).unwrap().insert(Pos(7)).unwrap();
+    let delta = old.diff(&server.snapshot().unwrap());
+    assert!(delta.spawned().is_empty());
+
+    // `a` was never spawned on the client, so its component has nowhere to go.
+    let mut client = registered_world();
+    assert!(matches!(
+        client.apply_delta(&delta),
+        Err(EntityError::DeadEntity)
+    ));
+
+    let b = client.spawn().unwrap().id();
+    assert!(!client.entity(b).unwrap().contains::<Pos>());
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "derive")]
+
+use std::marker::PhantomData;
+
+use ecs2::prelude::*;
+
+#[derive(Component, Debug, PartialEq)]
+struct Position(i32, i32);
+
+#[derive(Component, Debug, PartialEq)]
+#[component(storage = "sparse_set")]
+struct Velocity {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Component)]
+struct Marker<T: Send + Sync + 'static>(PhantomData<T>);
+
+#[derive(Unique, Default)]
+struct Score(u32);
+
+#[derive(WorldData, Default)]
+struct GameInfo {
+    frame: u64,
+}
+
+#[test]
+fn derived_components() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Position(1, 2))
+        .unwrap()
+        .insert(Velocity { x: 3, y: 4 })
+        .unwrap()
+        .insert(Marker::<u8>(PhantomData))
+        .unwrap()
+        .id();
+
+    let (positions, velocities, markers) = world
+        .borrow::<(
+            QueryComp<Position>,
+            QueryComp<Velocity>,
+            QueryComp<Marker<u8>>,
+        )>()
+        .unwrap();
+    assert_eq!(positions.get(a).unwrap(), &Position(1, 2));
+    assert_eq!(velocities.get(a).unwrap(), &Velocity { x: 3, y: 4 });
+    assert!(markers.get(a).is_ok());
+}
+
+#[test]
+fn derived_unique_and_world_data() {
+    let mut world = World::<GameInfo>::new();
+    world.insert_unique(Score(3));
+    world.data.borrow_mut().frame += 1;
+
+    assert_eq!(world.borrow::<QueryUnique<Score>>().unwrap().get().0, 3);
+    assert_eq!(world.data.borrow().frame, 1);
+}
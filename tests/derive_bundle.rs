@@ -0,0 +1,61 @@
+#![cfg(feature = "derive")]
+
+use ecs2::prelude::*;
+
+#[derive(Component, Debug, PartialEq)]
+struct Pos(i32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Vel(i32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Name(&'static str);
+
+#[derive(Bundle)]
+struct Body {
+    pos: Pos,
+    vel: Vel,
+}
+
+#[derive(Bundle)]
+struct Player(Body, Name);
+
+#[test]
+fn derived_bundle() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn_with(Player(
+            Body {
+                pos: Pos(1),
+                vel: Vel(2),
+            },
+            Name("player"),
+        ))
+        .unwrap()
+        .id();
+
+    let (pos, vel, name) = world
+        .borrow::<(QueryComp<Pos>, QueryComp<Vel>, QueryComp<Name>)>()
+        .unwrap();
+    assert_eq!(pos.get(a).unwrap(), &Pos(1));
+    assert_eq!(vel.get(a).unwrap(), &Vel(2));
+    assert_eq!(name.get(a).unwrap(), &Name("player"));
+}
+
+#[test]
+fn remove_derived_bundle() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn_with((Pos(1), Vel(2), Name("a")))
+        .unwrap()
+        .remove_bundle::<Body>()
+        .unwrap()
+        .id();
+
+    let (pos, vel, name) = world
+        .borrow::<(QueryComp<Pos>, QueryComp<Vel>, QueryComp<Name>)>()
+        .unwrap();
+    assert!(pos.get(a).is_err());
+    assert!(vel.get(a).is_err());
+    assert_eq!(name.get(a).unwrap(), &Name("a"));
+}
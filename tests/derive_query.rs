@@ -0,0 +1,136 @@
+#![cfg(feature = "derive")]
+
+use ecs2::prelude::*;
+use ecs2::query::access::Access;
+use ecs2::query::{AtomicRefMut, QueryError, QueryFamily, QueryResult};
+
+#[derive(Component, Debug, PartialEq)]
+struct Position(i32);
+
+#[derive(Component, Debug, PartialEq)]
+struct Velocity(i32);
+
+#[derive(Unique, Default)]
+struct Frame(u32);
+
+#[derive(Query)]
+struct Movement<'a> {
+    positions: QueryCompMut<'a, Position>,
+    velocities: QueryComp<'a, Velocity>,
+    frame: QueryUniqueMut<'a, Frame>,
+}
+
+#[derive(Query)]
+struct Positions<'w>(QueryComp<'w, Position>);
+
+fn movement(mut movement: Movement) {
+    for (_, mut pos, vel) in (&mut movement.positions, &movement.velocities).join() {
+        pos.0 += vel.0;
+    }
+    movement.frame.get_mut().0 += 1;
+}
+
+fn world() -> (World, EntityId) {
+    let mut world = World::new();
+    world.insert_unique(Frame::default());
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Position(0))
+        .unwrap()
+        .insert(Velocity(2))
+        .unwrap()
+        .id();
+    (world, a)
+}
+
+#[test]
+fn derived_query() {
+    let (world, a) = world();
+    world.run(movement).unwrap();
+
+    let Positions(positions) = world.borrow::<Positions>().unwrap();
+    assert_eq!(positions.get(a).unwrap(), &Position(2));
+    assert_eq!(world.borrow::<QueryUnique<Frame>>().unwrap().get().0, 1);
+}
+
+#[test]
+fn derived_query_in_schedule() {
+    let (world, a) = world();
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(movement).unwrap();
+    schedule.run(&world).unwrap();
+    schedule.run(&world).unwrap();
+
+    let positions = world.borrow::<QueryComp<Position>>().unwrap();
+    assert_eq!(positions.get(a).unwrap(), &Position(4));
+}
+
+#[test]
+fn first_error_is_returned() {
+    let world = World::<()>::new();
+    assert!(matches!(
+        world.borrow::<Movement>(),
+        Err(QueryError::StorageMissing)
+    ));
+}
+
+#[test]
+fn derived_access() {
+    let mut access = Access::new();
+    <Movement as Query<()>>::access(&mut access);
+    assert_eq!(access.iter().count(), 3);
+    access.validate().unwrap();
+
+    let mut schedule = Schedule::<()>::new();
+    assert!(schedule.add_system(|_: Movement, _: Positions| {}).is_err());
+}
+
+#[derive(WorldData, Default)]
+struct GameInfo {
+    name: String,
+}
+
+struct QueryGameInfo<'a>(AtomicRefMut<'a, GameInfo>);
+
+impl<'a> Query<'a, GameInfo> for QueryGameInfo<'a> {
+    fn borrow(world: &'a World<GameInfo>) -> QueryResult<Self> {
+        Ok(QueryGameInfo(world.data.try_borrow_mut()?))
+    }
+
+    fn access(access: &mut Access) {
+        access.write_data();
+    }
+}
+
+impl QueryFamily<GameInfo> for QueryGameInfo<'_> {
+    type Item<'a> = QueryGameInfo<'a>;
+}
+
+#[derive(Component)]
+struct Named(&'static str);
+
+#[derive(Query)]
+struct QueryNamedCmps<'a> {
+    info: QueryGameInfo<'a>,
+    cmps: QueryComp<'a, Named>,
+}
+
+#[test]
+fn composed_with_custom_query() {
+    let mut world = World::<GameInfo>::new();
+    world.spawn().unwrap().insert(Named("foo")).unwrap();
+
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(|mut named: QueryNamedCmps| {
+            for cmp in named.cmps.iter() {
+                named.info.0.name.push_str(cmp.0);
+            }
+        })
+        .unwrap();
+    schedule.run(&world).unwrap();
+
+    assert_eq!(world.data.borrow().name, "foo");
+}
@@ -0,0 +1,67 @@
+use ecs2::prelude::*;
+use ecs2::query::QueryError;
+use ecs2::storage::entities::EntityError;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Bar(usize);
+impl Component for Bar {}
+
+#[test]
+fn despawn_removes_components() {
+    let mut world = World::<()>::new();
+
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Foo(1))
+        .unwrap()
+        .insert(Bar(2))
+        .unwrap()
+        .id();
+    let b = world.spawn().unwrap().insert(Foo(3)).unwrap().id();
+
+    world.despawn(a).unwrap();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    let bars = world.borrow::<QueryComp<Bar>>().unwrap();
+
+    assert!(matches!(foos.get(a), Err(QueryError::EntityDead)));
+    assert_eq!(foos.get(b).unwrap(), &Foo(3));
+    assert_eq!(foos.iter().count(), 1);
+    assert_eq!(bars.iter().count(), 0);
+}
+
+#[test]
+fn despawn_dead_entity() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().id();
+    world.despawn(a).unwrap();
+
+    assert!(matches!(world.despawn(a), Err(EntityError::DeadEntity)));
+}
+
+#[test]
+fn recycled_entity_has_no_components() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    world
+        .spawn()
+        .unwrap()
+        .insert(Foo(2))
+        .unwrap()
+        .despawn()
+        .unwrap();
+    world.despawn(a).unwrap();
+
+    let b = world.spawn().unwrap().id();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    assert!(matches!(foos.get(b), Err(QueryError::EntityMissing)));
+    assert_eq!(foos.iter().count(), 0);
+}
@@ -0,0 +1,97 @@
+use ecs2::prelude::*;
+use ecs2::query::QueryError;
+use ecs2::storage::entities::EntityError;
+
+#[derive(Debug, PartialEq)]
+struct Pos(i32);
+impl Component for Pos {}
+
+#[derive(Debug, PartialEq)]
+struct Vel(i32);
+impl Component for Vel {}
+
+#[test]
+fn entity_ref() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(1)).unwrap().id();
+
+    let entity = world.entity(a).unwrap();
+    assert_eq!(entity.id(), a);
+    assert_eq!(*entity.get::<Pos>().unwrap(), Pos(1));
+    assert!(matches!(
+        entity.get::<Vel>(),
+        Err(QueryError::EntityMissing)
+    ));
+    assert!(entity.contains::<Pos>());
+    assert!(!entity.contains::<Vel>());
+}
+
+#[test]
+fn entity_ref_borrow_conflict() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(1)).unwrap().id();
+
+    let _pos = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    let entity = world.entity(a).unwrap();
+    assert!(matches!(
+        entity.get::<Pos>(),
+        Err(QueryError::BorrowError(_))
+    ));
+}
+
+#[test]
+#[should_panic]
+fn entity_ref_contains_while_borrowed() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(1)).unwrap().id();
+
+    let _pos = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    world.entity(a).unwrap().contains::<Pos>();
+}
+
+#[test]
+fn entity_mut() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(1)).unwrap().id();
+
+    let mut entity = world.entity_mut(a).unwrap();
+    entity.get_mut::<Pos>().unwrap().0 += 1;
+    assert_eq!(*entity.get::<Pos>().unwrap(), Pos(2));
+    assert!(entity.get_mut::<Vel>().is_err());
+
+    assert_eq!(entity.take::<Pos>(), Some(Pos(2)));
+    assert_eq!(entity.take::<Pos>(), None);
+    assert_eq!(entity.take::<Vel>(), None);
+    assert!(!entity.contains::<Pos>());
+
+    let removed = world.borrow::<RemovedComponents<Pos>>().unwrap();
+    let mut cursor = RemovedCursor::new();
+    assert_eq!(removed.read(&mut cursor).collect::<Vec<_>>(), vec![a]);
+}
+
+#[test]
+fn get_mut_marks_changed() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(1)).unwrap().id();
+
+    world.run(|_: QueryComp<Pos>| {}).unwrap();
+    let tick = world.change_tick();
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(pos.iter_changed_since(tick).count(), 0);
+    drop(pos);
+
+    world.entity_mut(a).unwrap().get_mut::<Pos>().unwrap().0 = 2;
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(pos.iter_changed_since(tick).count(), 1);
+}
+
+#[test]
+fn dead_entity() {
+    let mut world = World::<()>::new();
+    let a = world.spawn().unwrap().id();
+    world.despawn(a).unwrap();
+
+    assert!(matches!(world.entity(a), Err(EntityError::DeadEntity)));
+    assert!(matches!(world.entity_mut(a), Err(EntityError::DeadEntity)));
+}
@@ -0,0 +1,135 @@
+use ecs2::prelude::*;
+use ecs2::query::access::{Access, AccessKind};
+
+#[derive(Debug, PartialEq)]
+struct Health(u32);
+impl Component for Health {}
+
+#[derive(Debug, PartialEq)]
+struct Armor(u32);
+impl Component for Armor {}
+
+struct Enemy;
+impl Component for Enemy {}
+
+struct Dead;
+impl Component for Dead {}
+
+fn spawn_enemies(world: &mut World) -> [EntityId; 4] {
+    let alive = world.spawn_with((Health(10), Enemy)).unwrap().id();
+    let armored = world
+        .spawn_with((Health(20), Armor(5), Enemy))
+        .unwrap()
+        .id();
+    let dead = world.spawn_with((Health(0), Enemy, Dead)).unwrap().id();
+    let friend = world.spawn_with(Health(30)).unwrap().id();
+    [alive, armored, dead, friend]
+}
+
+#[test]
+fn with_and_without() {
+    let mut world = World::<()>::new();
+    let [alive, armored, dead, friend] = spawn_enemies(&mut world);
+
+    world
+        .run(
+            |mut health: QueryCompMut<Health>, enemy: With<Enemy>, not_dead: Without<Dead>| {
+                let mut entities = vec![];
+                for (entity, mut health, (), ()) in (&mut health, &enemy, &not_dead).join() {
+                    health.0 -= 1;
+                    entities.push(entity);
+                }
+                assert_eq!(entities, vec![alive, armored]);
+
+                assert!(enemy.matches(dead));
+                assert!(!enemy.matches(friend));
+                assert!(!not_dead.matches(dead));
+                assert!(not_dead.matches(friend));
+            },
+        )
+        .unwrap();
+
+    let health = world.borrow::<QueryComp<Health>>().unwrap();
+    assert_eq!(health.get(alive).unwrap(), &Health(9));
+    assert_eq!(health.get(armored).unwrap(), &Health(19));
+    assert_eq!(health.get(dead).unwrap(), &Health(0));
+    assert_eq!(health.get(friend).unwrap(), &Health(30));
+}
+
+#[test]
+fn optional() {
+    let mut world = World::<()>::new();
+    let [alive, armored, dead, friend] = spawn_enemies(&mut world);
+
+    world
+        .run(
+            |health: QueryComp<Health>, mut armor: QueryCompMut<Armor>| {
+                let items: Vec<_> = (&health, Optional(&armor))
+                    .join()
+                    .map(|(entity, health, armor)| (entity, health.0, armor.map(|armor| armor.0)))
+                    .collect();
+                assert_eq!(
+                    items,
+                    vec![
+                        (alive, 10, None),
+                        (armored, 20, Some(5)),
+                        (dead, 0, None),
+                        (friend, 30, None),
+                    ]
+                );
+
+                for (_, _, armor) in (&health, Optional(&mut armor)).join() {
+                    if let Some(mut armor) = armor {
+                        armor.0 += 1;
+                    }
+                }
+            },
+        )
+        .unwrap();
+
+    let armor = world.borrow::<QueryComp<Armor>>().unwrap();
+    assert_eq!(armor.get(armored).unwrap(), &Armor(6));
+}
+
+#[test]
+fn without_only_visits_every_entity() {
+    let mut world = World::<()>::new();
+    let [alive, armored, _, friend] = spawn_enemies(&mut world);
+    let empty = world.spawn().unwrap().id();
+    world.despawn(alive).unwrap();
+
+    world
+        .run(|not_dead: Without<Dead>, armor: QueryComp<Armor>| {
+            let items: Vec<_> = (&not_dead, Optional(&armor))
+                .join()
+                .map(|(entity, (), armor)| (entity, armor.is_some()))
+                .collect();
+            assert_eq!(
+                items,
+                vec![(armored, true), (friend, false), (empty, false)]
+            );
+        })
+        .unwrap();
+}
+
+#[test]
+fn filters_read_components() {
+    let mut access = Access::new();
+    <(With<Enemy>, Without<Dead>) as Query<()>>::access(&mut access);
+
+    let kinds: Vec<_> = access
+        .iter()
+        .map(|access| (access.name, access.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (std::any::type_name::<Enemy>(), AccessKind::Read),
+            (std::any::type_name::<Dead>(), AccessKind::Read),
+        ]
+    );
+
+    let mut writes_dead = Access::new();
+    writes_dead.write_component::<Dead>();
+    assert!(access.conflicts_with(&writes_dead));
+}
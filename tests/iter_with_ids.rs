@@ -0,0 +1,41 @@
+use ecs2::prelude::*;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+#[test]
+fn iter_with_ids() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    let b = world.spawn().unwrap().insert(Foo(2)).unwrap().id();
+    world.despawn(a).unwrap();
+    let c = world.spawn().unwrap().insert(Foo(3)).unwrap().id();
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    let ids: Vec<_> = foos.iter_with_ids().collect();
+
+    assert_eq!(ids, vec![(b, &Foo(2)), (c, &Foo(3))]);
+    for (entity, foo) in ids {
+        assert_eq!(foos.get(entity).unwrap(), foo);
+    }
+}
+
+#[test]
+fn iter_mut_with_ids() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    let b = world.spawn().unwrap().insert(Foo(2)).unwrap().id();
+
+    let mut foos = world.borrow::<QueryCompMut<Foo>>().unwrap();
+    for (entity, mut foo) in foos.iter_mut_with_ids() {
+        if entity == b {
+            foo.0 *= 10;
+        }
+    }
+
+    assert_eq!(foos.get(a).unwrap(), &Foo(1));
+    assert_eq!(foos.get(b).unwrap(), &Foo(20));
+}
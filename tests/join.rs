@@ -0,0 +1,93 @@
+use ecs2::prelude::*;
+
+#[derive(Debug, PartialEq)]
+struct Pos(f32);
+impl Component for Pos {}
+
+#[derive(Debug, PartialEq)]
+struct Vel(f32);
+impl Component for Vel {}
+
+#[derive(Debug, PartialEq)]
+struct Tag;
+impl Component for Tag {}
+
+#[test]
+fn join_two() {
+    let mut world = World::<()>::new();
+
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Pos(0.0))
+        .unwrap()
+        .insert(Vel(1.0))
+        .unwrap()
+        .id();
+    let b = world.spawn().unwrap().insert(Pos(5.0)).unwrap().id();
+    let c = world
+        .spawn()
+        .unwrap()
+        .insert(Vel(2.0))
+        .unwrap()
+        .insert(Pos(10.0))
+        .unwrap()
+        .id();
+
+    world
+        .run(|mut pos: QueryCompMut<Pos>, vel: QueryComp<Vel>| {
+            let mut entities = vec![];
+            for (entity, mut pos, vel) in (&mut pos, &vel).join() {
+                pos.0 += vel.0;
+                entities.push(entity);
+            }
+
+            assert_eq!(entities.len(), 2);
+            assert!(entities.contains(&a));
+            assert!(entities.contains(&c));
+        })
+        .unwrap();
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(pos.get(a).unwrap(), &Pos(1.0));
+    assert_eq!(pos.get(b).unwrap(), &Pos(5.0));
+    assert_eq!(pos.get(c).unwrap(), &Pos(12.0));
+}
+
+#[test]
+fn join_driven_by_smallest() {
+    let mut world = World::<()>::new();
+
+    for i in 0..10 {
+        world.spawn().unwrap().insert(Pos(i as f32)).unwrap();
+    }
+    let tagged = world
+        .spawn()
+        .unwrap()
+        .insert(Pos(20.0))
+        .unwrap()
+        .insert(Tag)
+        .unwrap()
+        .id();
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    let tags = world.borrow::<QueryComp<Tag>>().unwrap();
+
+    let mut iter = (&pos, &tags).join();
+    assert_eq!(iter.size_hint(), (0, Some(1)));
+    assert_eq!(iter.next(), Some((tagged, &Pos(20.0), &Tag)));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn join_skips_despawned() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Pos(1.0)).unwrap().id();
+    let b = world.spawn().unwrap().insert(Pos(2.0)).unwrap().id();
+    world.despawn(a).unwrap();
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    let joined: Vec<_> = (&pos,).join().collect();
+    assert_eq!(joined, vec![(b, &Pos(2.0))]);
+}
@@ -0,0 +1,124 @@
+use std::sync::Mutex;
+
+use ecs2::prelude::*;
+use ecs2::rayon::prelude::*;
+use ecs2::rayon::ThreadPoolBuilder;
+
+#[derive(Debug, PartialEq)]
+struct Pos(u64);
+impl Component for Pos {}
+
+#[derive(Debug, PartialEq)]
+struct Vel(u64);
+impl Component for Vel {}
+
+fn spawn_many(world: &mut World) -> Vec<EntityId> {
+    (0..10_000)
+        .map(|i| {
+            let entity = world.spawn().unwrap().insert(Pos(i)).unwrap();
+            if i % 2 == 0 {
+                entity.insert(Vel(1)).unwrap().id()
+            } else {
+                entity.id()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn par_iter_mut() {
+    let mut world = World::<()>::new();
+    spawn_many(&mut world);
+
+    let mut positions = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    positions.par_iter_mut().for_each(|mut pos| pos.0 *= 2);
+
+    let sum: u64 = positions.iter().map(|pos| pos.0).sum();
+    assert_eq!(sum, (0..10_000).map(|i| i * 2).sum());
+}
+
+#[test]
+fn par_for_each_with_ids() {
+    let mut world = World::<()>::new();
+    let entities = spawn_many(&mut world);
+
+    let seen = Mutex::new(vec![]);
+    let mut positions = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    positions.par_for_each_with_ids(|entity, pos| {
+        seen.lock().unwrap().push((pos.0, entity));
+    });
+
+    let mut seen = seen.into_inner().unwrap();
+    seen.sort_by_key(|(pos, _)| *pos);
+    let seen: Vec<_> = seen.into_iter().map(|(_, entity)| entity).collect();
+    assert_eq!(seen, entities);
+}
+
+#[test]
+fn par_for_each_marks_changed() {
+    let mut world = World::<()>::new();
+    spawn_many(&mut world);
+    world.run(|| {}).unwrap();
+
+    let tick = world.change_tick();
+    let mut positions = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    positions.par_for_each(|mut pos| {
+        if pos.0 % 10 == 0 {
+            pos.0 += 1;
+        }
+    });
+
+    assert_eq!(positions.iter_changed_since(tick).count(), 1_000);
+}
+
+#[test]
+fn par_join() {
+    let mut world = World::<()>::new();
+    spawn_many(&mut world);
+
+    let (mut positions, velocities) = world
+        .borrow::<(QueryCompMut<Pos>, QueryComp<Vel>)>()
+        .unwrap();
+    (&mut positions, &velocities)
+        .par_join()
+        .for_each(|(_, mut pos, vel)| pos.0 += vel.0);
+
+    let sum: u64 = positions.iter().map(|pos| pos.0).sum();
+    assert_eq!(sum, (0..10_000).sum::<u64>() + 5_000);
+}
+
+#[test]
+fn par_join_without_driver() {
+    let mut world = World::<()>::new();
+    spawn_many(&mut world);
+
+    // Neither member can drive the join, so every entity index is split between threads.
+    let (mut positions, no_velocity) = world.borrow::<(QueryCompMut<Pos>, Without<Vel>)>().unwrap();
+    let count = (Optional(&mut positions), &no_velocity)
+        .par_join()
+        .map(|(_, pos, ())| pos.unwrap().0 = u64::MAX)
+        .count();
+
+    assert_eq!(count, 5_000);
+    assert_eq!(
+        positions.iter().filter(|pos| pos.0 == u64::MAX).count(),
+        5_000
+    );
+}
+
+#[test]
+fn custom_thread_pool() {
+    let mut world = World::<()>::new();
+    spawn_many(&mut world);
+
+    let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    let mut positions = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    pool.install(|| {
+        positions.par_for_each(|mut pos| {
+            assert!(ecs2::rayon::current_thread_index().unwrap() < 2);
+            pos.0 = 0;
+        })
+    });
+
+    assert!(positions.iter().all(|pos| pos.0 == 0));
+}
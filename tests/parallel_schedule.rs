@@ -0,0 +1,179 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use ecs2::prelude::*;
+use ecs2::query::QueryError;
+use ecs2::schedule::ScheduleError;
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+impl Unique for Log {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Bar(usize);
+impl Component for Bar {}
+
+/// Wait until `count` systems have reached this point, returning whether they did
+/// before the timeout.
+fn rendezvous(arrived: &AtomicUsize, count: usize) -> bool {
+    arrived.fetch_add(1, Ordering::SeqCst);
+
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if arrived.load(Ordering::SeqCst) >= count {
+            return true;
+        }
+        std::thread::yield_now();
+    }
+
+    false
+}
+
+#[derive(Default)]
+struct Met(AtomicUsize);
+impl Unique for Met {}
+
+static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+
+fn update_foos(mut foos: QueryCompMut<Foo>, met: QueryUnique<Met>) {
+    for mut value in foos.iter_mut() {
+        value.0 += 1;
+    }
+    if rendezvous(&ARRIVED, 2) {
+        met.get().0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn update_bars(mut bars: QueryCompMut<Bar>, met: QueryUnique<Met>) {
+    for mut value in bars.iter_mut() {
+        value.0 += 1;
+    }
+    if rendezvous(&ARRIVED, 2) {
+        met.get().0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn disjoint_systems_run_concurrently() {
+    let mut world = World::<()>::new();
+    world.insert_unique(Met::default());
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Foo(0))
+        .unwrap()
+        .insert(Bar(0))
+        .unwrap()
+        .id();
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(update_foos).unwrap();
+    schedule.add_system(update_bars).unwrap();
+    schedule.run_parallel(&world).unwrap();
+
+    assert_eq!(
+        world
+            .borrow::<QueryUnique<Met>>()
+            .unwrap()
+            .get()
+            .0
+            .load(Ordering::SeqCst),
+        2
+    );
+
+    let (foos, bars) = world.borrow::<(QueryComp<Foo>, QueryComp<Bar>)>().unwrap();
+    assert_eq!(foos.get(a).unwrap(), &Foo(1));
+    assert_eq!(bars.get(a).unwrap(), &Bar(1));
+}
+
+fn first(mut log: QueryUniqueMut<Log>) {
+    log.get_mut().0.push("first");
+}
+
+fn second(mut log: QueryUniqueMut<Log>) {
+    log.get_mut().0.push("second");
+}
+
+fn third(mut log: QueryUniqueMut<Log>) {
+    log.get_mut().0.push("third");
+}
+
+#[test]
+fn conflicting_systems_keep_their_order() {
+    let mut world = World::<()>::new();
+    world.insert_unique(Log::default());
+
+    let mut schedule = Schedule::new();
+    let third_id = schedule.add_system(third).unwrap().id();
+    schedule.add_system(first).unwrap().before(third_id);
+    schedule.add_system(second).unwrap().before(third_id);
+
+    for _ in 0..2 {
+        schedule.run_parallel(&world).unwrap();
+    }
+
+    let log = world.borrow::<QueryUnique<Log>>().unwrap();
+    assert_eq!(
+        log.get().0,
+        ["first", "second", "third", "first", "second", "third"]
+    );
+}
+
+#[test]
+fn constraints_are_respected() {
+    let mut world = World::<()>::new();
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    world.spawn().unwrap().insert(Bar(0)).unwrap();
+
+    // The systems don't conflict, so only the constraint orders them.
+    let mut schedule = Schedule::new();
+    let double = schedule
+        .add_system(|mut foos: QueryCompMut<Foo>| {
+            for mut value in foos.iter_mut() {
+                value.0 *= 2;
+            }
+        })
+        .unwrap()
+        .id();
+    schedule
+        .add_system(|foos: QueryComp<Foo>, mut bars: QueryCompMut<Bar>| {
+            for mut value in bars.iter_mut() {
+                value.0 = foos.iter().map(|foo| foo.0).sum();
+            }
+        })
+        .unwrap()
+        .after(double);
+    schedule.run_parallel(&world).unwrap();
+
+    let (foos, bars) = world.borrow::<(QueryComp<Foo>, QueryComp<Bar>)>().unwrap();
+    assert_eq!(foos.get(a).unwrap(), &Foo(2));
+    assert_eq!(bars.iter().next().unwrap(), &Bar(2));
+}
+
+struct Missing;
+impl Unique for Missing {}
+
+fn needs_missing(_missing: QueryUnique<Missing>) {}
+
+#[test]
+fn failing_system_is_reported() {
+    let mut world = World::<()>::new();
+    world.insert_unique(Log::default());
+
+    let mut schedule = Schedule::new();
+    let first_id = schedule.add_system(first).unwrap().id();
+    schedule.add_system(needs_missing).unwrap().after(first_id);
+    schedule.add_system(third).unwrap();
+
+    match schedule.run_parallel(&world) {
+        Err(ScheduleError::System { name, error }) => {
+            assert!(name.ends_with("needs_missing"));
+            assert!(matches!(error, QueryError::StorageMissing));
+        }
+        _ => panic!("expected the system to fail"),
+    }
+}
@@ -0,0 +1,98 @@
+use ecs2::prelude::*;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+#[test]
+fn removals_are_recorded() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    let b = world.spawn().unwrap().insert(Foo(2)).unwrap().id();
+    let untouched = world.spawn().unwrap().id();
+
+    world
+        .run(|mut foos: QueryCompMut<Foo>| {
+            assert_eq!(foos.remove(a).unwrap(), Some(Foo(1)));
+        })
+        .unwrap();
+    world.despawn(b).unwrap();
+    world.despawn(untouched).unwrap();
+
+    let mut cursor = RemovedCursor::new();
+    let removed = world.borrow::<RemovedComponents<Foo>>().unwrap();
+    assert_eq!(removed.read(&mut cursor).collect::<Vec<_>>(), vec![a, b]);
+    assert_eq!(removed.read(&mut cursor).count(), 0);
+
+    let c = world
+        .spawn()
+        .unwrap()
+        .insert(Foo(3))
+        .unwrap()
+        .remove::<Foo>()
+        .unwrap()
+        .id();
+
+    let removed = world.borrow::<RemovedComponents<Foo>>().unwrap();
+    assert_eq!(removed.read(&mut cursor).collect::<Vec<_>>(), vec![c]);
+}
+
+#[test]
+fn readers_have_independent_cursors() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn().unwrap().insert(Foo(1)).unwrap().id();
+    world.despawn(a).unwrap();
+
+    let mut first = RemovedCursor::new();
+    let mut second = RemovedCursor::new();
+
+    let removed = world.borrow::<RemovedComponents<Foo>>().unwrap();
+    assert_eq!(removed.read(&mut first).collect::<Vec<_>>(), vec![a]);
+    assert_eq!(removed.read(&mut second).collect::<Vec<_>>(), vec![a]);
+
+    world.clear_removed_components();
+
+    let removed = world.borrow::<RemovedComponents<Foo>>().unwrap();
+    assert_eq!(removed.iter().count(), 0);
+    assert_eq!(removed.read(&mut first).count(), 0);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Bar(usize);
+impl Component for Bar {}
+
+#[test]
+fn removals_can_be_read_alongside_components() {
+    let mut world = World::<()>::new();
+
+    let a = world.spawn_with((Foo(1), Bar(1))).unwrap().id();
+    let b = world.spawn_with((Foo(2), Bar(2))).unwrap().id();
+    world.entity_mut(a).unwrap().remove::<Bar>().unwrap();
+
+    // A cleanup system that removes `Foo` from entities that lost their `Bar`.
+    fn cleanup(mut foos: QueryCompMut<Foo>, removed: RemovedComponents<Bar>) {
+        for entity in removed.iter() {
+            foos.remove(entity).unwrap();
+        }
+    }
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(cleanup).unwrap();
+    schedule.run(&world).unwrap();
+
+    assert!(!world.entity(a).unwrap().contains::<Foo>());
+    assert!(world.entity(b).unwrap().contains::<Foo>());
+
+    // The same component's removals can be read while removing it.
+    world
+        .run(
+            |mut foos: QueryCompMut<Foo>, removed: RemovedComponents<Foo>| {
+                assert_eq!(removed.iter().collect::<Vec<_>>(), vec![a]);
+                foos.remove(b).unwrap();
+                assert_eq!(removed.iter().collect::<Vec<_>>(), vec![a, b]);
+            },
+        )
+        .unwrap();
+}
@@ -0,0 +1,207 @@
+use ecs2::prelude::*;
+use ecs2::query::access::{Access, AccessKind};
+use ecs2::query::{AtomicRefMut, QueryError, QueryFamily, QueryResult};
+use ecs2::schedule::ScheduleError;
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+impl Unique for Log {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Foo(usize);
+impl Component for Foo {}
+
+fn first(mut log: QueryUniqueMut<Log>) {
+    log.get_mut().0.push("first");
+}
+
+fn second(mut log: QueryUniqueMut<Log>) {
+    log.get_mut().0.push("second");
+}
+
+fn third(mut log: QueryUniqueMut<Log>) {
+    log.get_mut().0.push("third");
+}
+
+fn world_with_log() -> World {
+    let mut world = World::new();
+    world.insert_unique(Log::default());
+    world
+}
+
+#[test]
+fn insertion_order() {
+    let world = world_with_log();
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(first).unwrap();
+    schedule.add_system(second).unwrap();
+    schedule.add_system(third).unwrap();
+    schedule.run(&world).unwrap();
+
+    let log = world.borrow::<QueryUnique<Log>>().unwrap();
+    assert_eq!(log.get().0, ["first", "second", "third"]);
+}
+
+#[test]
+fn before_and_after() {
+    let world = world_with_log();
+
+    let mut schedule = Schedule::new();
+    let third_id = schedule.add_system(third).unwrap().id();
+    let first_id = schedule.add_system(first).unwrap().before(third_id).id();
+    schedule
+        .add_system(second)
+        .unwrap()
+        .after(first_id)
+        .before(third_id);
+    schedule.run(&world).unwrap();
+
+    let log = world.borrow::<QueryUnique<Log>>().unwrap();
+    assert_eq!(log.get().0, ["first", "second", "third"]);
+}
+
+#[test]
+fn cycle() {
+    let world = world_with_log();
+
+    let mut schedule = Schedule::new();
+    let first_id = schedule.add_system(first).unwrap().id();
+    schedule
+        .add_system(second)
+        .unwrap()
+        .before(first_id)
+        .after(first_id);
+
+    assert!(matches!(schedule.run(&world), Err(ScheduleError::Cycle)));
+    assert!(world
+        .borrow::<QueryUnique<Log>>()
+        .unwrap()
+        .get()
+        .0
+        .is_empty());
+}
+
+#[test]
+fn constraint_on_system_from_another_schedule() {
+    let world = world_with_log();
+
+    let mut other: Schedule = Schedule::new();
+    other.add_system(first).unwrap();
+    other.add_system(second).unwrap();
+    let third_id = other.add_system(third).unwrap().id();
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(first).unwrap().before(third_id);
+
+    assert!(matches!(
+        schedule.run(&world),
+        Err(ScheduleError::UnknownSystem)
+    ));
+    assert!(matches!(
+        schedule.run_parallel(&world),
+        Err(ScheduleError::UnknownSystem)
+    ));
+}
+
+struct Missing;
+impl Unique for Missing {}
+
+fn needs_missing(_missing: QueryUnique<Missing>) {}
+
+#[test]
+fn failing_system_is_reported() {
+    let world = world_with_log();
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(first).unwrap();
+    schedule.add_system(needs_missing).unwrap();
+    schedule.add_system(third).unwrap();
+
+    match schedule.run(&world) {
+        Err(ScheduleError::System { name, error }) => {
+            assert!(name.ends_with("needs_missing"));
+            assert!(matches!(error, QueryError::StorageMissing));
+        }
+        _ => panic!("expected the system to fail"),
+    }
+
+    let log = world.borrow::<QueryUnique<Log>>().unwrap();
+    assert_eq!(log.get().0, ["first"]);
+}
+
+fn conflicting(_q1: QueryCompMut<Foo>, _q2: QueryCompMut<Foo>) {}
+
+#[test]
+fn conflicting_system_is_rejected() {
+    let mut schedule = Schedule::<()>::new();
+
+    match schedule.add_system(conflicting) {
+        Err(ScheduleError::Conflict { name, error }) => {
+            assert!(name.ends_with("conflicting"));
+            assert_eq!(error.first.kind, AccessKind::Write);
+            assert_eq!(error.second.kind, AccessKind::Write);
+        }
+        _ => panic!("expected the system to be rejected"),
+    }
+
+    assert!(schedule.is_empty());
+}
+
+#[test]
+fn run_repeatedly() {
+    let mut world = World::<()>::new();
+    let a = world.spawn().unwrap().insert(Foo(0)).unwrap().id();
+
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(|mut foos: QueryCompMut<Foo>| {
+            for mut foo in foos.iter_mut() {
+                foo.0 += 1;
+            }
+        })
+        .unwrap();
+
+    let start = world.change_tick();
+    for _ in 0..3 {
+        schedule.run(&world).unwrap();
+    }
+
+    let foos = world.borrow::<QueryComp<Foo>>().unwrap();
+    assert_eq!(foos.get(a).unwrap(), &Foo(3));
+    assert!(world.change_tick() > start);
+}
+
+#[derive(Default)]
+struct Counter(usize);
+impl WorldData for Counter {}
+
+struct QueryCounter<'a>(AtomicRefMut<'a, Counter>);
+
+impl<'a> Query<'a, Counter> for QueryCounter<'a> {
+    fn borrow(world: &'a World<Counter>) -> QueryResult<Self> {
+        Ok(QueryCounter(world.data.try_borrow_mut()?))
+    }
+
+    fn access(access: &mut Access) {
+        access.write_data();
+    }
+}
+
+impl QueryFamily<Counter> for QueryCounter<'_> {
+    type Item<'a> = QueryCounter<'a>;
+}
+
+#[test]
+fn custom_query() {
+    let world = World::<Counter>::new();
+
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(|mut counter: QueryCounter| counter.0 .0 += 1)
+        .unwrap();
+    schedule.run(&world).unwrap();
+    schedule.run(&world).unwrap();
+
+    assert_eq!(world.data.borrow().0, 2);
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "serde")]
+
+use ecs2::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Pos(i32, i32);
+impl Component for Pos {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Name(String);
+impl Component for Name {}
+
+#[derive(Debug, PartialEq)]
+struct Unsaved;
+impl Component for Unsaved {}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Score(u32);
+impl Unique for Score {}
+
+fn registered_world() -> World {
+    let mut world = World::new();
+    world.register_component::<Pos>("pos").serde();
+    world.register_component::<Name>("name").serde();
+    world.register_unique::<Score>("score").serde();
+    world
+}
+
+fn to_json(world: &World) -> String {
+    let mut json = vec![];
+    world
+        .serialize(&mut serde_json::Serializer::new(&mut json))
+        .unwrap();
+    String::from_utf8(json).unwrap()
+}
+
+fn from_json(world: &mut World, json: &str) -> serde_json::Result<()> {
+    world.deserialize(&mut serde_json::Deserializer::from_str(json))
+}
+
+#[test]
+fn round_trip() {
+    let mut world = registered_world();
+    let a = world
+        .spawn_with((Pos(1, 2), Name("a".to_owned())))
+        .unwrap()
+        .id();
+    let dead = world.spawn_with(Pos(0, 0)).unwrap().id();
+    let b = world.spawn_with((Pos(3, 4), Unsaved)).unwrap().id();
+    world.despawn(dead).unwrap();
+    world.insert_unique(Score(10));
+
+    let json = to_json(&world);
+
+    let mut loaded = registered_world();
+    from_json(&mut loaded, &json).unwrap();
+
+    assert!(loaded.entity(a).is_ok());
+    assert!(loaded.entity(b).is_ok());
+    assert!(loaded.entity(dead).is_err());
+
+    let a_ref = loaded.entity(a).unwrap();
+    assert_eq!(*a_ref.get::<Pos>().unwrap(), Pos(1, 2));
+    assert_eq!(*a_ref.get::<Name>().unwrap(), Name("a".to_owned()));
+
+    let b_ref = loaded.entity(b).unwrap();
+    assert_eq!(*b_ref.get::<Pos>().unwrap(), Pos(3, 4));
+    assert!(!b_ref.contains::<Name>());
+    assert!(!b_ref.contains::<Unsaved>());
+
+    let score = loaded.borrow::<QueryUnique<Score>>().unwrap();
+    assert_eq!(score.get(), &Score(10));
+    drop(score);
+
+    // The allocator state is restored, so both worlds hand out the same ids.
+    assert_eq!(world.spawn().unwrap().id(), loaded.spawn().unwrap().id());
+    assert_eq!(to_json(&world), to_json(&loaded));
+}
+
+#[test]
+fn reserved_entities_are_saved_as_alive() {
+    let mut world = registered_world();
+    let a = world.spawn().unwrap().id();
+    world.despawn(a).unwrap();
+    let reused = world.reserve_entity().unwrap();
+    let new = world.reserve_entity().unwrap();
+
+    let mut loaded = registered_world();
+    from_json(&mut loaded, &to_json(&world)).unwrap();
+    assert!(loaded.entity(reused).is_ok());
+    assert!(loaded.entity(new).is_ok());
+}
+
+#[test]
+fn deserialize_replaces_entities_and_components() {
+    let mut world = registered_world();
+    let a = world.spawn_with(Pos(1, 1)).unwrap().id();
+    let json = to_json(&world);
+
+    let mut loaded = registered_world();
+    loaded.insert_unique(Score(1));
+    let old = loaded.spawn().unwrap().id();
+    let old = loaded
+        .entity_mut(old)
+        .unwrap()
+        .insert(Unsaved)
+        .unwrap()
+        .insert(Pos(5, 5))
+        .unwrap()
+        .id();
+    loaded.spawn().unwrap();
+
+    from_json(&mut loaded, &json).unwrap();
+
+    // `old` had the same id as `a`.
+    assert_eq!(old, a);
+    let a_ref = loaded.entity(a).unwrap();
+    assert_eq!(*a_ref.get::<Pos>().unwrap(), Pos(1, 1));
+    assert!(!a_ref.contains::<Unsaved>());
+
+    // Uniques that weren't saved are kept.
+    let score = loaded.borrow::<QueryUnique<Score>>().unwrap();
+    assert_eq!(score.get(), &Score(1));
+    drop(score);
+
+    let pos = loaded.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(pos.iter().count(), 1);
+}
+
+#[test]
+fn unknown_component_fails_without_changes() {
+    let mut world = registered_world();
+    world.spawn_with(Name("a".to_owned())).unwrap();
+    let json = to_json(&world);
+
+    let mut loaded = World::<()>::new();
+    loaded.register_component::<Pos>("pos").serde();
+    let a = loaded.spawn_with(Pos(1, 1)).unwrap().id();
+    let b = loaded.spawn().unwrap().id();
+
+    let error = from_json(&mut loaded, &json).unwrap_err();
+    assert!(error.to_string().contains("unknown component `name`"));
+
+    assert!(loaded.entity(b).is_ok());
+    let pos = loaded.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(pos.get(a).unwrap(), &Pos(1, 1));
+}
+
+#[test]
+fn inconsistent_data_is_rejected() {
+    let mut world = registered_world();
+
+    let dead_component = r#"{"entities":{"entries":[[0,false]],"free":[1]},"components":{"pos":[[1,[0,0]]]},"uniques":{}}"#;
+    assert!(from_json(&mut world, dead_component).is_err());
+
+    let alive_free =
+        r#"{"entities":{"entries":[[0,true]],"free":[1]},"components":{},"uniques":{}}"#;
+    assert!(from_json(&mut world, alive_free).is_err());
+
+    // Despawning an entity at the maximum version would overflow it.
+    let alive_max_version =
+        r#"{"entities":{"entries":[[4294967295,true]],"free":[]},"components":{},"uniques":{}}"#;
+    assert!(from_json(&mut world, alive_max_version).is_err());
+
+    let free_max_version =
+        r#"{"entities":{"entries":[[4294967295,false]],"free":[1]},"components":{},"uniques":{}}"#;
+    assert!(from_json(&mut world, free_max_version).is_err());
+
+    // A retired index at the maximum version is fine, as long as it isn't free.
+    let retired =
+        r#"{"entities":{"entries":[[4294967295,false]],"free":[]},"components":{},"uniques":{}}"#;
+    from_json(&mut world, retired).unwrap();
+
+    let valid = r#"{"entities":{"entries":[[0,true]],"free":[]},"components":{"pos":[[1,[2,3]]]},"uniques":{}}"#;
+    from_json(&mut world, valid).unwrap();
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(pos.iter().collect::<Vec<_>>(), vec![&Pos(2, 3)]);
+}
+
+#[test]
+#[should_panic]
+fn name_taken_by_another_type() {
+    let mut world = World::<()>::new();
+    world.register_component::<Pos>("pos");
+    world.register_component::<Name>("pos");
+}
@@ -0,0 +1,163 @@
+use ecs2::prelude::*;
+use ecs2::snapshot::SnapshotBuffer;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pos(i32);
+impl Component for Pos {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Vel(i32);
+impl Component for Vel {}
+
+#[derive(Debug, PartialEq)]
+struct Uncloneable(i32);
+impl Component for Uncloneable {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Frame(u64);
+impl Unique for Frame {}
+
+fn registered_world() -> World {
+    let mut world = World::new();
+    world.register_component::<Pos>("pos").cloneable();
+    world.register_component::<Vel>("vel").cloneable();
+    world.register_unique::<Frame>("frame").cloneable();
+    world
+}
+
+fn step(world: &World) {
+    world
+        .run(|mut pos: QueryCompMut<Pos>, vel: QueryComp<Vel>| {
+            for (_, mut pos, vel) in (&mut pos, &vel).join() {
+                pos.0 += vel.0;
+            }
+        })
+        .unwrap();
+    world
+        .run(|mut frame: QueryUniqueMut<Frame>| frame.get_mut().0 += 1)
+        .unwrap();
+}
+
+#[test]
+fn restore() {
+    let mut world = registered_world();
+    world.insert_unique(Frame(0));
+    let a = world.spawn_with((Pos(0), Vel(1))).unwrap().id();
+    let b = world.spawn_with((Pos(10), Uncloneable(1))).unwrap().id();
+    let snapshot = world.snapshot().unwrap();
+    let next = world.spawn().unwrap().id();
+
+    step(&world);
+    world.despawn(b).unwrap();
+    let c = world.spawn_with((Pos(20), Uncloneable(2))).unwrap().id();
+    world.entity_mut(a).unwrap().insert(Uncloneable(3)).unwrap();
+
+    world.restore(&snapshot);
+
+    assert_eq!(*world.entity(a).unwrap().get::<Pos>().unwrap(), Pos(0));
+    assert_eq!(*world.entity(b).unwrap().get::<Pos>().unwrap(), Pos(10));
+    assert!(world.entity(c).is_err());
+
+    // Uncloneable components are kept on entities that survived, and can't be brought
+    // back for ones that didn't.
+    assert!(world.entity(a).unwrap().contains::<Uncloneable>());
+    assert!(!world.entity(b).unwrap().contains::<Uncloneable>());
+
+    let frame = world.borrow::<QueryUnique<Frame>>().unwrap();
+    assert_eq!(frame.get(), &Frame(0));
+    drop(frame);
+
+    // The allocator is restored too, so the same entity is handed out next.
+    assert_eq!(world.spawn().unwrap().id(), next);
+}
+
+#[test]
+fn restore_missing_storages() {
+    let mut world = registered_world();
+    let a = world.spawn().unwrap().id();
+    let snapshot = world.snapshot().unwrap();
+
+    world.entity_mut(a).unwrap().insert(Pos(1)).unwrap();
+    world.insert_unique(Frame(1));
+    world.restore(&snapshot);
+
+    assert!(!world.entity(a).unwrap().contains::<Pos>());
+    assert!(!world.contains_unique::<Frame>());
+}
+
+#[test]
+fn restore_tracks_changes() {
+    let mut world = registered_world();
+    world.insert_unique(Frame(0));
+    let a = world.spawn_with(Pos(1)).unwrap().id();
+    let b = world.spawn_with(Vel(1)).unwrap().id();
+    let snapshot = world.snapshot().unwrap();
+
+    world.entity_mut(a).unwrap().insert(Pos(99)).unwrap();
+    world.entity_mut(a).unwrap().insert(Vel(2)).unwrap();
+    world.entity_mut(b).unwrap().remove::<Vel>().unwrap();
+    world.clear_removed_components();
+    step(&world);
+
+    let tick = world.change_tick();
+    world.restore(&snapshot);
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert_eq!(
+        pos.iter_changed_since(tick).collect::<Vec<_>>(),
+        vec![(a, &Pos(1))]
+    );
+    assert_eq!(pos.iter_added_since(tick).count(), 0);
+    drop(pos);
+
+    // `b`'s velocity is brought back, and `a`'s is dropped.
+    let vel = world.borrow::<QueryComp<Vel>>().unwrap();
+    assert_eq!(
+        vel.iter_added_since(tick).collect::<Vec<_>>(),
+        vec![(b, &Vel(1))]
+    );
+    drop(vel);
+
+    let removed = world.borrow::<RemovedComponents<Vel>>().unwrap();
+    assert_eq!(removed.iter().collect::<Vec<_>>(), vec![a]);
+}
+
+#[test]
+fn rollback() {
+    let mut world = registered_world();
+    world.insert_unique(Frame(0));
+    let a = world.spawn_with((Pos(0), Vel(1))).unwrap().id();
+
+    let mut buffer = SnapshotBuffer::new(4);
+    for frame in 0..10 {
+        buffer.push(frame, world.snapshot().unwrap());
+        step(&world);
+    }
+
+    assert_eq!(buffer.len(), 4);
+    assert_eq!(buffer.frames().collect::<Vec<_>>(), vec![6, 7, 8, 9]);
+    assert!(buffer.get(5).is_none());
+
+    world.restore(buffer.get(7).unwrap());
+    assert_eq!(*world.entity(a).unwrap().get::<Pos>().unwrap(), Pos(7));
+
+    // Re-simulating from frame 7 replaces the later snapshots.
+    world.entity_mut(a).unwrap().insert(Vel(2)).unwrap();
+    buffer.push(7, world.snapshot().unwrap());
+    assert_eq!(buffer.frames().collect::<Vec<_>>(), vec![6, 7]);
+    assert_eq!(buffer.latest().unwrap().0, 7);
+
+    step(&world);
+    world.restore(buffer.get(7).unwrap());
+    assert_eq!(*world.entity(a).unwrap().get::<Vel>().unwrap(), Vel(2));
+    assert_eq!(*world.entity(a).unwrap().get::<Pos>().unwrap(), Pos(7));
+}
+
+#[test]
+fn snapshot_fails_while_borrowed() {
+    let mut world = registered_world();
+    world.spawn_with(Pos(0)).unwrap();
+
+    let _pos = world.borrow::<QueryCompMut<Pos>>().unwrap();
+    assert!(world.snapshot().is_err());
+}
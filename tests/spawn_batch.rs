@@ -0,0 +1,67 @@
+use ecs2::prelude::*;
+
+#[derive(Debug, PartialEq)]
+struct Pos(i32);
+impl Component for Pos {}
+
+#[derive(Debug, PartialEq)]
+struct Vel(i32);
+impl Component for Vel {}
+
+#[test]
+fn spawn_batch() {
+    let mut world = World::<()>::new();
+    let entities = world
+        .spawn_batch((0..10_000).map(|i| (Pos(i), Vel(-i))))
+        .unwrap();
+    assert_eq!(entities.len(), 10_000);
+
+    let (pos, vel) = world.borrow::<(QueryComp<Pos>, QueryComp<Vel>)>().unwrap();
+    for (i, &entity) in entities.iter().enumerate() {
+        assert_eq!(pos.get(entity).unwrap(), &Pos(i as i32));
+        assert_eq!(vel.get(entity).unwrap(), &Vel(-(i as i32)));
+    }
+}
+
+#[test]
+fn spawn_batch_reuses_despawned_entities() {
+    let mut world = World::<()>::new();
+    let a = world.spawn_with(Pos(0)).unwrap().id();
+    let b = world.spawn_with(Pos(1)).unwrap().id();
+    world.despawn(a).unwrap();
+
+    let entities = world.spawn_batch([Pos(2), Pos(3)]).unwrap();
+    assert_ne!(entities[0], a);
+    assert_ne!(entities[1], a);
+
+    let c = world.spawn_with(Pos(4)).unwrap().id();
+    assert!(!entities.contains(&c));
+    assert_ne!(c, b);
+
+    let pos = world.borrow::<QueryComp<Pos>>().unwrap();
+    assert!(pos.get(a).is_err());
+    assert_eq!(pos.get(b).unwrap(), &Pos(1));
+    assert_eq!(pos.get(entities[0]).unwrap(), &Pos(2));
+    assert_eq!(pos.get(entities[1]).unwrap(), &Pos(3));
+    assert_eq!(pos.get(c).unwrap(), &Pos(4));
+    assert_eq!(pos.iter().count(), 4);
+}
+
+#[test]
+fn spawn_empty_batch() {
+    let mut world = World::<()>::new();
+    let entities = world.spawn_batch(std::iter::empty::<Pos>()).unwrap();
+    assert!(entities.is_empty());
+}
+
+#[test]
+fn duplicate_component_types_dont_leak_entities() {
+    let mut world = World::<()>::new();
+    let spawned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = world.spawn_batch((0..3).map(|i| (Pos(i), Pos(-i))));
+    }));
+    assert!(spawned.is_err());
+
+    let expected = World::<()>::new().spawn().unwrap().id();
+    assert_eq!(world.spawn().unwrap().id(), expected);
+}
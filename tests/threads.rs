@@ -0,0 +1,135 @@
+use std::thread;
+
+use ecs2::prelude::*;
+use ecs2::query::QueryError;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Position(i32);
+impl Component for Position {}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Velocity(i32);
+impl Component for Velocity {}
+
+#[derive(Default)]
+struct Data;
+impl WorldData for Data {}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn world_is_send_and_sync() {
+    assert_send_sync::<World>();
+    assert_send_sync::<World<Data>>();
+}
+
+#[test]
+fn shared_reads_from_many_threads() {
+    let mut world = World::<()>::new();
+    for i in 0..100 {
+        world.spawn().unwrap().insert(Position(i)).unwrap();
+    }
+
+    let sums: Vec<i32> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    let positions = world.borrow::<QueryComp<Position>>().unwrap();
+                    positions.iter().map(|pos| pos.0).sum()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    assert_eq!(sums, [4950; 4]);
+}
+
+#[test]
+fn disjoint_writes_from_many_threads() {
+    let mut world = World::<()>::new();
+    let a = world
+        .spawn()
+        .unwrap()
+        .insert(Position(0))
+        .unwrap()
+        .insert(Velocity(0))
+        .unwrap()
+        .id();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut positions = world.borrow::<QueryCompMut<Position>>().unwrap();
+            positions.get_mut(a).unwrap().0 = 1;
+        });
+        scope.spawn(|| {
+            let mut velocities = world.borrow::<QueryCompMut<Velocity>>().unwrap();
+            velocities.get_mut(a).unwrap().0 = 2;
+        });
+    });
+
+    let (positions, velocities) = world
+        .borrow::<(QueryComp<Position>, QueryComp<Velocity>)>()
+        .unwrap();
+    assert_eq!(positions.get(a).unwrap(), &Position(1));
+    assert_eq!(velocities.get(a).unwrap(), &Velocity(2));
+}
+
+#[test]
+fn conflicts_across_threads() {
+    let mut world = World::<Data>::new();
+    world.spawn().unwrap().insert(Position(0)).unwrap();
+
+    let positions = world.borrow::<QueryComp<Position>>().unwrap();
+    let _data = world.data.borrow_mut();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            assert!(matches!(
+                world.borrow::<QueryCompMut<Position>>(),
+                Err(QueryError::BorrowMutError(_))
+            ));
+            assert!(world.data.try_borrow().is_err());
+        });
+    });
+
+    drop(positions);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            world.borrow::<QueryCompMut<Position>>().unwrap();
+        });
+    });
+}
+
+#[test]
+fn storages_created_from_many_threads() {
+    let world = World::<()>::new();
+
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                let positions = world.borrow::<QueryComp<Position>>().unwrap();
+                assert_eq!(positions.iter().count(), 0);
+            });
+        }
+    });
+
+    let mut world = world;
+    let a = world.spawn().unwrap().insert(Position(3)).unwrap().id();
+    let positions = world.borrow::<QueryComp<Position>>().unwrap();
+    assert_eq!(positions.get(a).unwrap(), &Position(3));
+}
+
+#[test]
+fn runs_from_many_threads() {
+    let world = World::<()>::new();
+    let start = world.change_tick();
+
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| world.run(|| {}).unwrap());
+        }
+    });
+
+    assert!(world.change_tick() > start);
+}
@@ -0,0 +1,63 @@
+use ecs2::prelude::*;
+use ecs2::query::QueryError;
+
+#[derive(Debug, Default, PartialEq)]
+struct Score(u32);
+impl Unique for Score {}
+
+#[test]
+fn insert_replaces() {
+    let mut world = World::<()>::new();
+    assert_eq!(world.insert_unique(Score(1)), None);
+    assert_eq!(world.insert_unique(Score(2)), Some(Score(1)));
+
+    let score = world.borrow::<QueryUnique<Score>>().unwrap();
+    assert_eq!(score.get(), &Score(2));
+}
+
+#[test]
+fn remove_unique() {
+    let mut world = World::<()>::new();
+    assert_eq!(world.remove_unique::<Score>(), None);
+
+    world.insert_unique(Score(1));
+    assert!(world.contains_unique::<Score>());
+    assert_eq!(world.remove_unique::<Score>(), Some(Score(1)));
+    assert!(!world.contains_unique::<Score>());
+
+    assert!(matches!(
+        world.borrow::<QueryUnique<Score>>(),
+        Err(QueryError::StorageMissing)
+    ));
+
+    world.insert_unique(Score(2));
+    let score = world.borrow::<QueryUnique<Score>>().unwrap();
+    assert_eq!(score.get(), &Score(2));
+}
+
+#[test]
+fn init_unique() {
+    let mut world = World::<()>::new();
+    world.init_unique::<Score>();
+    assert_eq!(world.remove_unique::<Score>(), Some(Score(0)));
+
+    world.insert_unique(Score(3));
+    world.init_unique::<Score>();
+    assert_eq!(world.remove_unique::<Score>(), Some(Score(3)));
+}
+
+#[test]
+fn optional_unique() {
+    let mut world = World::<()>::new();
+    world
+        .run(|score: OptionalUnique<Score>| assert_eq!(score.get(), None))
+        .unwrap();
+
+    world.insert_unique(Score(1));
+    world
+        .run(|score: OptionalUnique<Score>| assert_eq!(score.get(), Some(&Score(1))))
+        .unwrap();
+
+    let _score = world.borrow::<QueryUniqueMut<Score>>().unwrap();
+    assert!(world.borrow::<OptionalUnique<Score>>().is_err());
+}